@@ -0,0 +1,276 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bstr::ByteSlice as _;
+
+use crate::{
+    Pid,
+    event::Event,
+    files::{FilesCollector, ProcessFiles},
+};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub enum DepsFormat {
+    #[default]
+    Depfile,
+    Dot,
+}
+
+#[derive(Debug, Default)]
+pub struct DepsOutputOptions {
+    pub format: DepsFormat,
+
+    /// Only write a single depfile rule for the first exec'd process with
+    /// this pid, including all of its descendants.
+    pub subtree: Option<Pid>,
+
+    /// Include files under `/dev`, `/proc`, and `/sys`.
+    pub include_system_files: bool,
+}
+
+/// Writes the dependencies between exec'd processes and files, either as
+/// a Makefile-style depfile or as a GraphViz DOT graph. Like
+/// [`crate::files::FilesOutput`], nothing gets written until
+/// [`DepsOutput::finish`] is called.
+pub struct DepsOutput<W: std::io::Write> {
+    writer: W,
+    options: DepsOutputOptions,
+    collector: FilesCollector,
+}
+
+impl<W: std::io::Write> DepsOutput<W> {
+    pub fn new(writer: W, options: DepsOutputOptions) -> Self {
+        Self {
+            writer,
+            options,
+            collector: FilesCollector::default(),
+        }
+    }
+
+    pub fn output_event(&mut self, event: Event) {
+        self.collector.add_event(event);
+    }
+
+    pub fn finish(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        match self.options.format {
+            DepsFormat::Depfile => self.write_depfile()?,
+            DepsFormat::Dot => self.write_dot()?,
+        }
+
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_depfile(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let processes = self.collector.processes();
+
+        if let Some(subtree_pid) = self.options.subtree {
+            let root_index = processes
+                .iter()
+                .position(|process| process.pid == subtree_pid)
+                .ok_or_else(|| format!("no exec'd process found with pid {subtree_pid}"))?;
+            let deps = ProcessDeps::from_processes(
+                subtree_indices(processes, root_index).map(|index| &processes[index]),
+                self.options.include_system_files,
+            );
+            write_depfile_rule(&mut self.writer, &deps)?;
+        } else {
+            for process in processes {
+                let deps = ProcessDeps::from_processes(
+                    std::iter::once(process),
+                    self.options.include_system_files,
+                );
+                write_depfile_rule(&mut self.writer, &deps)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_dot(&mut self) -> std::io::Result<()> {
+        let processes = self.collector.processes();
+        let process_deps: Vec<_> = processes
+            .iter()
+            .map(|process| {
+                ProcessDeps::from_processes(
+                    std::iter::once(process),
+                    self.options.include_system_files,
+                )
+            })
+            .collect();
+
+        // Processes use the node ids `p0`, `p1`, etc., and files use `f0`,
+        // `f1`, etc. (with the path as the label), so a file can't be
+        // mistaken for a process
+        let file_ids: BTreeMap<&bstr::BString, usize> = process_deps
+            .iter()
+            .flat_map(|deps| deps.inputs.iter().chain(&deps.outputs))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(id, path)| (path, id))
+            .collect();
+
+        writeln!(self.writer, "digraph build {{")?;
+
+        for (index, (process, deps)) in processes.iter().zip(&process_deps).enumerate() {
+            let command = process
+                .command
+                .as_ref()
+                .map(|command| {
+                    command
+                        .rsplit_once_str("/")
+                        .map_or(command.as_bstr(), |(_, name)| name.as_bstr())
+                })
+                .unwrap_or_default();
+            let label = format!("{command} ({})", process.pid);
+            write!(self.writer, "  p{index} [shape=box, label=")?;
+            write_dot_string(&mut self.writer, label.as_bytes().as_bstr())?;
+            writeln!(self.writer, "];")?;
+
+            if let Some(parent_index) = process.parent_index {
+                writeln!(self.writer, "  p{parent_index} -> p{index} [style=dashed];")?;
+            }
+
+            for input in &deps.inputs {
+                writeln!(self.writer, "  f{} -> p{index};", file_ids[input])?;
+            }
+            for output in &deps.outputs {
+                writeln!(self.writer, "  p{index} -> f{};", file_ids[output])?;
+            }
+        }
+
+        for (path, id) in &file_ids {
+            write!(self.writer, "  f{id} [shape=note, label=")?;
+            write_dot_string(&mut self.writer, path.as_bstr())?;
+            writeln!(self.writer, "];")?;
+        }
+
+        writeln!(self.writer, "}}")?;
+
+        Ok(())
+    }
+}
+
+/// The inputs and outputs of one or more exec'd processes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProcessDeps {
+    /// Files that were read, excluding files that were created or deleted
+    /// by the processes themselves.
+    pub inputs: BTreeSet<bstr::BString>,
+
+    /// Files that were written or created, excluding files that were
+    /// deleted afterwards (e.g. temporary files).
+    pub outputs: BTreeSet<bstr::BString>,
+}
+
+impl ProcessDeps {
+    pub fn from_processes<'a>(
+        processes: impl IntoIterator<Item = &'a ProcessFiles>,
+        include_system_files: bool,
+    ) -> Self {
+        let mut read = BTreeSet::new();
+        let mut written = BTreeSet::new();
+        let mut created = BTreeSet::new();
+        let mut deleted = BTreeSet::new();
+        for process in processes {
+            read.extend(process.read.iter().cloned());
+            written.extend(process.written.iter().cloned());
+            created.extend(process.created.iter().cloned());
+            deleted.extend(process.deleted.iter().cloned());
+        }
+
+        let is_included =
+            |path: &bstr::BString| include_system_files || !is_system_file(path.as_bstr());
+        let inputs = read
+            .into_iter()
+            .filter(|path| is_included(path) && !created.contains(path) && !deleted.contains(path))
+            .collect();
+        let outputs = written
+            .into_iter()
+            .chain(created)
+            .filter(|path| is_included(path) && !deleted.contains(path))
+            .collect();
+
+        Self { inputs, outputs }
+    }
+}
+
+/// Get the index of a process and all of its descendants.
+fn subtree_indices(processes: &[ProcessFiles], root_index: usize) -> impl Iterator<Item = usize> {
+    // Parents are always exec'd before their children, so one pass is
+    // enough to find every descendant
+    let mut in_subtree = vec![false; processes.len()];
+    in_subtree[root_index] = true;
+    for index in root_index + 1..processes.len() {
+        in_subtree[index] = processes[index]
+            .parent_index
+            .is_some_and(|parent_index| in_subtree[parent_index]);
+    }
+
+    in_subtree
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, in_subtree)| in_subtree.then_some(index))
+}
+
+fn is_system_file(path: &bstr::BStr) -> bool {
+    ["/dev", "/proc", "/sys"].iter().any(|dir| {
+        path.strip_prefix(dir.as_bytes())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(b"/"))
+    })
+}
+
+/// Write a depfile rule, with the outputs as targets and the inputs as
+/// prerequisites. Processes without any outputs are skipped, since a rule
+/// needs at least one target.
+fn write_depfile_rule(writer: &mut impl std::io::Write, deps: &ProcessDeps) -> std::io::Result<()> {
+    if deps.outputs.is_empty() {
+        return Ok(());
+    }
+
+    for (index, output) in deps.outputs.iter().enumerate() {
+        if index > 0 {
+            write!(writer, " ")?;
+        }
+        write_depfile_path(writer, output.as_bstr())?;
+    }
+    write!(writer, ":")?;
+    for input in &deps.inputs {
+        write!(writer, " \\\n  ")?;
+        write_depfile_path(writer, input.as_bstr())?;
+    }
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+/// Write a path in a depfile, escaping it the way Make and Ninja expect.
+fn write_depfile_path(writer: &mut impl std::io::Write, path: &bstr::BStr) -> std::io::Result<()> {
+    for &byte in path.iter() {
+        match byte {
+            b' ' => writer.write_all(b"\\ ")?,
+            b'#' => writer.write_all(b"\\#")?,
+            b'$' => writer.write_all(b"$$")?,
+            byte => writer.write_all(&[byte])?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a quoted DOT string. Invalid UTF-8 gets replaced with the
+/// replacement character.
+fn write_dot_string(writer: &mut impl std::io::Write, value: &bstr::BStr) -> std::io::Result<()> {
+    write!(writer, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            c => write!(writer, "{c}")?,
+        }
+    }
+    write!(writer, "\"")?;
+
+    Ok(())
+}
@@ -6,10 +6,28 @@ use crate::Pid;
 pub struct Event<'a> {
     pub timestamp: jiff::Timestamp,
     pub pid: Pid,
+
+    /// The thread group id of the process, which is the pid of the thread
+    /// group leader. Same as `pid` unless the event came from a thread.
+    pub tgid: Pid,
     pub parent_pid: Option<Pid>,
     pub owner_pid: Option<Pid>,
     pub strace: crate::strace::Line<'a>,
     pub kind: EventKind,
+
+    /// The path arguments of the syscall, resolved to absolute paths when
+    /// the process's cwd (or the syscall's dir fd) is known. Arguments that
+    /// aren't strings (such as `NULL`) are `None`.
+    pub paths: Vec<Option<bstr::BString>>,
+
+    /// The path of the file descriptor read from or written to by `read`,
+    /// `write`, and similar syscalls, if it's known.
+    pub fd_path: Option<bstr::BString>,
+
+    /// The number of file descriptors the process has open after the event.
+    /// Only counts fds opened while the process was traced, so fds
+    /// inherited from an untraced parent (like stdio) aren't included.
+    pub open_fds: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -17,6 +35,11 @@ pub enum EventKind {
     ForkProcess(ForkProcessEvent),
     ExecProcess(ExecProcessEvent),
     StopProcess(StopProcessEvent),
+    FileAccess(FileAccessEvent),
+    Signal(SignalEvent),
+    SuspendProcess(SuspendProcessEvent),
+    ContinueProcess(ContinueProcessEvent),
+    ReapProcess(ReapProcessEvent),
     Log,
 }
 
@@ -24,6 +47,18 @@ pub enum EventKind {
 pub struct ForkProcessEvent {
     pub child_pid: Pid,
     pub child_owner_pid: Option<Pid>,
+
+    /// The thread group id of the child. Same as `child_pid` unless the
+    /// child is a thread.
+    pub child_tgid: Pid,
+
+    /// Whether the child is a new thread in the parent's thread group
+    /// (`CLONE_THREAD`) rather than a new process.
+    pub thread: bool,
+
+    /// Whether the child shares the parent's memory (`CLONE_VM`, or
+    /// `vfork`).
+    pub shared_memory: bool,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -55,6 +90,10 @@ impl ProcessExec {
 pub struct StopProcessEvent {
     pub stopped: ProcessStoppedReason,
     pub did_exec: bool,
+
+    /// Whether a thread stopped, rather than a thread group leader. The
+    /// rest of the process may still be running.
+    pub thread: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -62,3 +101,125 @@ pub enum ProcessStoppedReason {
     Exited { code: Option<i32> },
     Killed { signal: Option<String> },
 }
+
+#[derive(Debug, Clone)]
+pub struct FileAccessEvent {
+    /// The files accessed by the syscall. Most syscalls only access one
+    /// file, but e.g. `rename` deletes one path and creates another.
+    pub accesses: Vec<FileAccess>,
+
+    /// The flags the file was opened with (e.g. `O_RDONLY|O_CLOEXEC`), for
+    /// `open` and similar syscalls.
+    pub flags: Option<String>,
+
+    /// Whether the syscall succeeded.
+    pub success: bool,
+
+    /// The errno name if the syscall failed (e.g. `ENOENT`).
+    pub error: Option<String>,
+}
+
+/// A signal delivered to a process.
+#[derive(Debug, Clone)]
+pub struct SignalEvent {
+    /// The signal name, such as `SIGTERM`.
+    pub signal: String,
+
+    /// Where the signal came from (`si_code`), such as `SI_USER` or
+    /// `CLD_EXITED`.
+    pub code: Option<String>,
+
+    /// The pid of the process that sent the signal (`si_pid`). For
+    /// `SIGCHLD`, this is the child that changed state.
+    pub sender_pid: Option<Pid>,
+
+    /// The exit code or signal of the child for `SIGCHLD` (`si_status`).
+    pub status: Option<String>,
+
+    /// The `kill`, `tkill`, or `tgkill` syscall that sent the signal, if it
+    /// was traced.
+    pub sent_by: Option<SignalSyscall>,
+}
+
+/// A process reaped one of its children (or an orphaned descendant) with
+/// `wait4`, `waitpid`, or `waitid`.
+#[derive(Debug, Clone)]
+pub struct ReapProcessEvent {
+    pub child_pid: Pid,
+
+    /// When the child exited. This is `None` if the child's exit wasn't
+    /// traced.
+    pub exited_at: Option<jiff::Timestamp>,
+
+    /// When the wait syscall returned with the child.
+    pub reaped_at: jiff::Timestamp,
+
+    /// How long the wait syscall blocked for.
+    pub wait_duration: std::time::Duration,
+
+    /// Whether the child was reaped by a process other than its parent,
+    /// such as a subreaper after the parent exited.
+    pub orphan: bool,
+}
+
+/// A process was suspended by a job-control signal, such as `SIGSTOP` or
+/// `SIGTSTP`.
+#[derive(Debug, Clone)]
+pub struct SuspendProcessEvent {
+    pub signal: String,
+}
+
+/// A suspended process was continued by `SIGCONT`.
+#[derive(Debug, Clone)]
+pub struct ContinueProcessEvent {
+    /// When the process was suspended.
+    pub suspended_at: jiff::Timestamp,
+
+    /// The `SIGCONT` signal that continued the process.
+    pub signal: SignalEvent,
+}
+
+/// A syscall that sent a signal to another process.
+#[derive(Debug, Clone)]
+pub struct SignalSyscall {
+    pub pid: Pid,
+
+    /// The exec'd process that owns the sender, like [`Event::owner_pid`].
+    pub owner_pid: Option<Pid>,
+    pub timestamp: jiff::Timestamp,
+    pub syscall: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileAccess {
+    pub path: bstr::BString,
+    pub mode: FileAccessMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileAccessMode {
+    Read,
+    Write,
+
+    /// The file may have been created. Files opened with `O_CREAT` count as
+    /// created, even if the file already existed. Without `O_EXCL`, they
+    /// also count as read or written based on the open flags.
+    Create,
+    Delete,
+
+    /// Only the file's metadata was read or changed (e.g. `stat` or
+    /// `chmod`).
+    Metadata,
+}
+
+impl FileAccessMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Create => "create",
+            Self::Delete => "delete",
+            Self::Metadata => "metadata",
+        }
+    }
+}
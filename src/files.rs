@@ -0,0 +1,290 @@
+use std::collections::{BTreeSet, HashMap};
+
+use bstr::ByteSlice as _;
+
+use crate::{
+    Pid,
+    event::{Event, EventKind, FileAccessMode},
+};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub enum FilesFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+#[derive(Debug, Default)]
+pub struct FilesOutputOptions {
+    pub format: FilesFormat,
+}
+
+/// Writes the files accessed by each exec'd process. Unlike the other
+/// outputs, nothing gets written until [`FilesOutput::finish`] is called.
+pub struct FilesOutput<W: std::io::Write> {
+    writer: W,
+    options: FilesOutputOptions,
+    collector: FilesCollector,
+}
+
+impl<W: std::io::Write> FilesOutput<W> {
+    pub fn new(writer: W, options: FilesOutputOptions) -> Self {
+        Self {
+            writer,
+            options,
+            collector: FilesCollector::default(),
+        }
+    }
+
+    pub fn output_event(&mut self, event: Event) {
+        self.collector.add_event(event);
+    }
+
+    /// Get the processes seen so far, in the order they were exec'd.
+    pub fn processes(&self) -> &[ProcessFiles] {
+        self.collector.processes()
+    }
+
+    /// Write the report of all processes.
+    pub fn finish(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        match self.options.format {
+            FilesFormat::Table => self.write_table()?,
+            FilesFormat::Json => self.write_json()?,
+        }
+
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_table(&mut self) -> std::io::Result<()> {
+        for (index, process) in self.collector.processes.iter().enumerate() {
+            if index > 0 {
+                writeln!(self.writer)?;
+            }
+
+            let command = process
+                .command
+                .as_ref()
+                .map(|command| command.to_str_lossy())
+                .unwrap_or_default();
+            writeln!(self.writer, "{} {command}", process.pid)?;
+
+            if let Some(args) = &process.args {
+                let args = bstr::join(" ", args);
+                writeln!(self.writer, "  {:<10} {}", "args", args.as_bstr())?;
+            }
+
+            for (label, files) in process.file_sets() {
+                for file in files {
+                    writeln!(self.writer, "  {label:<10} {}", file.as_bstr())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_json(&mut self) -> std::io::Result<()> {
+        write!(self.writer, "[")?;
+
+        for (index, process) in self.collector.processes.iter().enumerate() {
+            if index > 0 {
+                write!(self.writer, ",")?;
+            }
+
+            write!(self.writer, "\n  {{\"pid\": {}", process.pid)?;
+
+            let parent_pid = process
+                .parent_index
+                .map(|parent_index| self.collector.processes[parent_index].pid);
+            if let Some(parent_pid) = parent_pid {
+                write!(self.writer, ", \"parent_pid\": {parent_pid}")?;
+            } else {
+                write!(self.writer, ", \"parent_pid\": null")?;
+            }
+
+            write!(self.writer, ", \"command\": ")?;
+            if let Some(command) = &process.command {
+                write_json_string(&mut self.writer, command.as_bstr())?;
+            } else {
+                write!(self.writer, "null")?;
+            }
+
+            write!(self.writer, ", \"args\": ")?;
+            if let Some(args) = &process.args {
+                write_json_string_array(&mut self.writer, args)?;
+            } else {
+                write!(self.writer, "null")?;
+            }
+
+            for (label, files) in process.file_sets() {
+                write!(self.writer, ", \"{}\": ", label.replace(' ', "_"))?;
+                write_json_string_array(&mut self.writer, files)?;
+            }
+
+            write!(self.writer, "}}")?;
+        }
+
+        if !self.collector.processes.is_empty() {
+            writeln!(self.writer)?;
+        }
+        writeln!(self.writer, "]")?;
+
+        Ok(())
+    }
+}
+
+/// Collects the files accessed by each exec'd process from a stream of
+/// events.
+#[derive(Debug, Default)]
+pub struct FilesCollector {
+    processes: Vec<ProcessFiles>,
+    current_processes: HashMap<Pid, usize>,
+}
+
+impl FilesCollector {
+    pub fn add_event(&mut self, event: Event) {
+        match event.kind {
+            EventKind::ExecProcess(exec_process_event) => {
+                let parent_index = event
+                    .owner_pid
+                    .and_then(|owner_pid| self.current_processes.get(&owner_pid))
+                    .copied();
+
+                self.current_processes
+                    .insert(event.pid, self.processes.len());
+                self.processes.push(ProcessFiles {
+                    pid: event.pid,
+                    parent_index,
+                    command: exec_process_event.exec.command,
+                    args: exec_process_event.exec.args,
+                    read: BTreeSet::new(),
+                    written: BTreeSet::new(),
+                    created: BTreeSet::new(),
+                    deleted: BTreeSet::new(),
+                    not_found: BTreeSet::new(),
+                });
+            }
+            EventKind::StopProcess(stop_process_event) if !stop_process_event.thread => {
+                self.current_processes.remove(&event.tgid);
+            }
+            EventKind::StopProcess(_) => {}
+            EventKind::FileAccess(file_access_event) => {
+                // Attribute the access to the process, or to the process
+                // that owns it if it hasn't exec'd
+                let process_index = self
+                    .current_processes
+                    .get(&event.pid)
+                    .or_else(|| self.current_processes.get(&event.owner_pid?));
+                let Some(&process_index) = process_index else {
+                    return;
+                };
+                let process = &mut self.processes[process_index];
+
+                for access in file_access_event.accesses {
+                    if !file_access_event.success {
+                        if matches!(
+                            file_access_event.error.as_deref(),
+                            Some("ENOENT" | "ENOTDIR")
+                        ) {
+                            process.not_found.insert(access.path);
+                        }
+                        continue;
+                    }
+
+                    let files = match access.mode {
+                        FileAccessMode::Read => &mut process.read,
+                        FileAccessMode::Write => &mut process.written,
+                        FileAccessMode::Create => &mut process.created,
+                        FileAccessMode::Delete => &mut process.deleted,
+                        FileAccessMode::Metadata => continue,
+                    };
+                    files.insert(access.path);
+                }
+            }
+            EventKind::ForkProcess(_)
+            | EventKind::Signal(_)
+            | EventKind::SuspendProcess(_)
+            | EventKind::ContinueProcess(_)
+            | EventKind::ReapProcess(_)
+            | EventKind::Log => {}
+        }
+    }
+
+    /// Get the processes seen so far, in the order they were exec'd.
+    pub fn processes(&self) -> &[ProcessFiles] {
+        &self.processes
+    }
+
+    pub fn into_processes(self) -> Vec<ProcessFiles> {
+        self.processes
+    }
+}
+
+/// The files accessed by an exec'd process, including accesses from any
+/// forked children that didn't exec.
+#[derive(Debug, Clone)]
+pub struct ProcessFiles {
+    pub pid: Pid,
+
+    /// The index of the exec'd process that started this one.
+    pub parent_index: Option<usize>,
+
+    pub command: Option<bstr::BString>,
+    pub args: Option<Vec<bstr::BString>>,
+    pub read: BTreeSet<bstr::BString>,
+    pub written: BTreeSet<bstr::BString>,
+    pub created: BTreeSet<bstr::BString>,
+    pub deleted: BTreeSet<bstr::BString>,
+
+    /// Files that were probed but didn't exist (`ENOENT` or `ENOTDIR`).
+    pub not_found: BTreeSet<bstr::BString>,
+}
+
+impl ProcessFiles {
+    fn file_sets(&self) -> [(&'static str, &BTreeSet<bstr::BString>); 5] {
+        [
+            ("read", &self.read),
+            ("written", &self.written),
+            ("created", &self.created),
+            ("deleted", &self.deleted),
+            ("not found", &self.not_found),
+        ]
+    }
+}
+
+fn write_json_string_array<'a>(
+    writer: &mut impl std::io::Write,
+    values: impl IntoIterator<Item = &'a bstr::BString>,
+) -> std::io::Result<()> {
+    write!(writer, "[")?;
+    for (index, value) in values.into_iter().enumerate() {
+        if index > 0 {
+            write!(writer, ", ")?;
+        }
+        write_json_string(writer, value.as_bstr())?;
+    }
+    write!(writer, "]")?;
+
+    Ok(())
+}
+
+/// Write a JSON string. Invalid UTF-8 gets replaced with the replacement
+/// character.
+fn write_json_string(writer: &mut impl std::io::Write, value: &bstr::BStr) -> std::io::Result<()> {
+    write!(writer, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            '\r' => write!(writer, "\\r")?,
+            '\t' => write!(writer, "\\t")?,
+            c if c.is_control() => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{c}")?,
+        }
+    }
+    write!(writer, "\"")?;
+
+    Ok(())
+}
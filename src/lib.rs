@@ -1,4 +1,6 @@
+pub mod deps;
 pub mod event;
+pub mod files;
 pub mod otel;
 pub mod perfetto;
 pub mod strace;
@@ -1,6 +1,6 @@
 use std::{
     collections::BTreeMap,
-    io::{BufRead as _, Write as _},
+    io::{BufRead as _, Seek as _, Write as _},
     path::PathBuf,
     process::ExitCode,
 };
@@ -18,7 +18,7 @@ const WINDOW_SIZE: usize = 100;
 /// strace output is expected in a specific format, essentially with the
 /// following flags:
 ///
-/// > strace -fe 'status=!unfinished' -Ttttyyv -s 4096
+/// > strace -f -Ttttyyv -s 4096
 #[derive(Debug, Clone, Parser)]
 struct Args {
     #[command(subcommand)]
@@ -35,6 +35,14 @@ enum Command {
     #[command(name = "strace2otel")]
     StraceToOtel(StraceToOtelArgs),
 
+    /// List the files read, written, created, and deleted by each process
+    /// from strace output
+    Files(FilesArgs),
+
+    /// Write the inputs and outputs of each process from strace output as a
+    /// depfile (for Make or Ninja) or as a GraphViz graph
+    Deps(DepsArgs),
+
     /// Run a process via strace
     ///
     /// Sets defaults for appropriate parsing. Write output verbatim, or
@@ -44,9 +52,8 @@ enum Command {
 
 #[derive(Debug, Clone, Parser)]
 struct StraceToPerfettoArgs {
-    /// The strace file to parse (defaults to stdin)
-    #[arg(default_value_t)]
-    input: patharg::InputArg,
+    #[command(flatten)]
+    input: StraceInputArgs,
 
     /// The Perfetto file to write
     #[arg(short, long)]
@@ -55,13 +62,26 @@ struct StraceToPerfettoArgs {
     /// Write logs ("Android logs" in the Perfetto UI)
     #[arg(short, long)]
     logs: bool,
+
+    /// Write a slice for each syscall, nested in the slice of its process
+    #[arg(long)]
+    syscalls: bool,
+
+    /// Only write slices for the named syscalls (implies `--syscalls`). Can
+    /// be passed multiple times or as a comma-separated list.
+    #[arg(long = "syscall", value_name = "NAME", value_delimiter = ',')]
+    syscall_filter: Vec<String>,
+
+    /// Write counter tracks for live processes, and for the open fds,
+    /// syscalls, and bytes read and written by each process
+    #[arg(long)]
+    counters: bool,
 }
 
 #[derive(Debug, Clone, Parser)]
 struct StraceToOtelArgs {
-    /// The strace file to parse (defaults to stdin)
-    #[arg(default_value_t)]
-    input: patharg::InputArg,
+    #[command(flatten)]
+    input: StraceInputArgs,
 
     /// Write logs in addition to traces/spans
     #[arg(short, long)]
@@ -71,6 +91,584 @@ struct StraceToOtelArgs {
     /// Can be useful if the OTel endpoint ignores old traces.
     #[arg(long)]
     relative_to_now: bool,
+
+    #[command(flatten)]
+    otel: OtelArgs,
+}
+
+#[derive(Debug, Clone, Parser)]
+struct OtelArgs {
+    /// The protocol to send OTLP data with
+    #[arg(long, value_enum, env = "OTEL_EXPORTER_OTLP_PROTOCOL", default_value_t)]
+    otel_protocol: OtelProtocol,
+
+    /// The base URL of the OTLP endpoint (defaults to
+    /// `$OTEL_EXPORTER_OTLP_ENDPOINT`, or the default endpoint for the
+    /// protocol)
+    #[arg(long)]
+    otel_endpoint: Option<String>,
+
+    /// Send a header with each OTLP request. Can be passed multiple times.
+    #[arg(long = "otel-header", value_name = "NAME=VALUE", value_parser = parse_key_value)]
+    otel_headers: Vec<(String, String)>,
+
+    /// The `service.name` resource attribute (defaults to
+    /// `$OTEL_SERVICE_NAME`, then `service.name` from
+    /// `$OTEL_RESOURCE_ATTRIBUTES`, or `systrument`)
+    #[arg(long)]
+    otel_service_name: Option<String>,
+
+    /// Set a resource attribute for all traces and logs. Can be passed
+    /// multiple times.
+    #[arg(
+        long = "otel-resource-attribute",
+        value_name = "NAME=VALUE",
+        value_parser = parse_key_value
+    )]
+    otel_resource_attributes: Vec<(String, String)>,
+
+    /// Add each syscall as an event on the span of its process
+    #[arg(long)]
+    otel_syscall_events: bool,
+
+    /// The maximum number of syscall events to add to each span
+    #[arg(long, default_value_t = 1000)]
+    otel_max_syscall_events: usize,
+
+    /// Use the OpenTelemetry semantic conventions for process attributes
+    /// (`process.pid`, `process.exit.code`, etc.), and mark spans of failed
+    /// processes as errors
+    #[arg(long)]
+    otel_semantic_conventions: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OtelProtocol {
+    /// Protobuf over HTTP
+    #[default]
+    #[value(name = "http/protobuf")]
+    HttpProtobuf,
+
+    /// JSON over HTTP
+    #[value(name = "http/json")]
+    HttpJson,
+
+    /// gRPC
+    #[value(name = "grpc")]
+    Grpc,
+}
+
+impl OtelArgs {
+    fn resource(&self) -> opentelemetry_sdk::Resource {
+        // The default resource includes `$OTEL_SERVICE_NAME` and
+        // `$OTEL_RESOURCE_ATTRIBUTES`
+        let mut resource = opentelemetry_sdk::Resource::builder();
+        if let Some(service_name) = &self.otel_service_name {
+            resource = resource.with_service_name(service_name.clone());
+        } else if !env_sets_service_name() {
+            resource = resource.with_service_name("systrument");
+        }
+
+        resource
+            .with_attributes(
+                self.otel_resource_attributes
+                    .iter()
+                    .map(|(name, value)| opentelemetry::KeyValue::new(name.clone(), value.clone())),
+            )
+            .build()
+    }
+
+    fn output_options(
+        &self,
+        relative_to: Option<jiff::Timestamp>,
+    ) -> systrument::otel::OtelOutputOptions {
+        systrument::otel::OtelOutputOptions {
+            relative_to,
+            syscall_events: self.otel_syscall_events,
+            max_syscall_events_per_span: Some(self.otel_max_syscall_events),
+            semantic_conventions: self.otel_semantic_conventions,
+        }
+    }
+
+    fn tracer_provider(
+        &self,
+        span_exporter: opentelemetry_otlp::SpanExporter,
+    ) -> opentelemetry_sdk::trace::SdkTracerProvider {
+        let mut builder = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(self.resource());
+
+        // Make room for syscall events, on top of the SDK's default limit
+        // of 128 events per span (used for signals, etc.)
+        if self.otel_syscall_events {
+            let max_events = self.otel_max_syscall_events.saturating_add(128);
+            builder = builder.with_max_events_per_span(max_events.try_into().unwrap_or(u32::MAX));
+        }
+
+        builder.build()
+    }
+
+    /// Start a Tokio runtime if needed by the exporter. The gRPC exporter
+    /// has to be built within a runtime, and the runtime has to outlive
+    /// the exporter.
+    fn tokio_runtime(&self) -> miette::Result<Option<tokio::runtime::Runtime>> {
+        if self.otel_protocol != OtelProtocol::Grpc {
+            return Ok(None);
+        }
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .into_diagnostic()
+            .wrap_err("failed to start Tokio runtime for OTLP gRPC exporter")?;
+        Ok(Some(runtime))
+    }
+
+    fn span_exporter(&self) -> miette::Result<opentelemetry_otlp::SpanExporter> {
+        use opentelemetry_otlp::{
+            WithExportConfig as _, WithHttpConfig as _, WithTonicConfig as _,
+        };
+
+        let span_exporter = match self.otel_protocol {
+            OtelProtocol::HttpProtobuf | OtelProtocol::HttpJson => {
+                let mut builder = opentelemetry_otlp::SpanExporter::builder()
+                    .with_http()
+                    .with_protocol(self.otel_protocol.into())
+                    .with_headers(self.otel_headers.iter().cloned().collect());
+                if let Some(endpoint) = self.http_endpoint("traces") {
+                    builder = builder.with_endpoint(endpoint);
+                }
+                builder.build()
+            }
+            OtelProtocol::Grpc => {
+                let mut builder = opentelemetry_otlp::SpanExporter::builder()
+                    .with_tonic()
+                    .with_metadata(self.grpc_metadata()?);
+                if let Some(endpoint) = &self.otel_endpoint {
+                    builder = builder.with_endpoint(endpoint);
+                }
+                builder.build()
+            }
+        };
+
+        span_exporter
+            .into_diagnostic()
+            .wrap_err("failed to build OTLP span exporter")
+    }
+
+    fn log_exporter(&self) -> miette::Result<opentelemetry_otlp::LogExporter> {
+        use opentelemetry_otlp::{
+            WithExportConfig as _, WithHttpConfig as _, WithTonicConfig as _,
+        };
+
+        let log_exporter = match self.otel_protocol {
+            OtelProtocol::HttpProtobuf | OtelProtocol::HttpJson => {
+                let mut builder = opentelemetry_otlp::LogExporter::builder()
+                    .with_http()
+                    .with_protocol(self.otel_protocol.into())
+                    .with_headers(self.otel_headers.iter().cloned().collect());
+                if let Some(endpoint) = self.http_endpoint("logs") {
+                    builder = builder.with_endpoint(endpoint);
+                }
+                builder.build()
+            }
+            OtelProtocol::Grpc => {
+                let mut builder = opentelemetry_otlp::LogExporter::builder()
+                    .with_tonic()
+                    .with_metadata(self.grpc_metadata()?);
+                if let Some(endpoint) = &self.otel_endpoint {
+                    builder = builder.with_endpoint(endpoint);
+                }
+                builder.build()
+            }
+        };
+
+        log_exporter
+            .into_diagnostic()
+            .wrap_err("failed to build OTLP log exporter")
+    }
+
+    /// Get the HTTP URL for a signal (`traces` or `logs`). Like with
+    /// `$OTEL_EXPORTER_OTLP_ENDPOINT`, the endpoint is a base URL, so the
+    /// signal's path gets appended.
+    fn http_endpoint(&self, signal: &str) -> Option<String> {
+        let endpoint = self.otel_endpoint.as_ref()?;
+        let endpoint = endpoint.trim_end_matches('/');
+        Some(format!("{endpoint}/v1/{signal}"))
+    }
+
+    fn grpc_metadata(
+        &self,
+    ) -> miette::Result<opentelemetry_otlp::tonic_types::metadata::MetadataMap> {
+        use opentelemetry_otlp::tonic_types::metadata::{MetadataKey, MetadataMap, MetadataValue};
+
+        let mut metadata = MetadataMap::new();
+        for (name, value) in &self.otel_headers {
+            let key = MetadataKey::from_bytes(name.as_bytes())
+                .into_diagnostic()
+                .wrap_err_with(|| format!("invalid OTLP header name {name:?}"))?;
+            let value = MetadataValue::try_from(value.as_str())
+                .into_diagnostic()
+                .wrap_err_with(|| format!("invalid value for OTLP header {name:?}"))?;
+            metadata.insert(key, value);
+        }
+
+        Ok(metadata)
+    }
+}
+
+impl From<OtelProtocol> for opentelemetry_otlp::Protocol {
+    fn from(protocol: OtelProtocol) -> Self {
+        match protocol {
+            OtelProtocol::HttpProtobuf => Self::HttpBinary,
+            OtelProtocol::HttpJson => Self::HttpJson,
+            OtelProtocol::Grpc => Self::Grpc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
+struct FilesArgs {
+    #[command(flatten)]
+    input: StraceInputArgs,
+
+    /// The file to write the report to (defaults to stdout)
+    #[arg(short, long, default_value_t)]
+    output: patharg::OutputArg,
+
+    /// The format of the report
+    #[arg(long, value_enum, default_value_t)]
+    format: FilesFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, clap::ValueEnum)]
+enum FilesFormat {
+    /// A human-readable table
+    #[default]
+    Table,
+
+    /// A JSON array, with one object per process
+    Json,
+}
+
+impl From<FilesFormat> for systrument::files::FilesFormat {
+    fn from(format: FilesFormat) -> Self {
+        match format {
+            FilesFormat::Table => Self::Table,
+            FilesFormat::Json => Self::Json,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
+struct DepsArgs {
+    #[command(flatten)]
+    input: StraceInputArgs,
+
+    /// The file to write to (defaults to stdout)
+    #[arg(short, long, default_value_t)]
+    output: patharg::OutputArg,
+
+    /// The format to write
+    #[arg(long, value_enum, default_value_t)]
+    format: DepsFormat,
+
+    /// Write a single depfile rule for the process with this pid and all of
+    /// its descendants, instead of one rule per process
+    #[arg(long, value_name = "PID")]
+    subtree: Option<systrument::Pid>,
+
+    /// Include files under `/dev`, `/proc`, and `/sys`
+    #[arg(long)]
+    include_system_files: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, clap::ValueEnum)]
+enum DepsFormat {
+    /// A Makefile-style depfile, with one rule per process
+    #[default]
+    Depfile,
+
+    /// A GraphViz DOT graph of all processes and files
+    Dot,
+}
+
+impl From<DepsFormat> for systrument::deps::DepsFormat {
+    fn from(format: DepsFormat) -> Self {
+        match format {
+            DepsFormat::Depfile => Self::Depfile,
+            DepsFormat::Dot => Self::Dot,
+        }
+    }
+}
+
+#[derive(Debug, Clone, clap::Args)]
+struct StraceInputArgs {
+    /// The strace file to parse (defaults to stdin). Can also be a directory
+    /// or output prefix used with `strace -ff -o <prefix>`, which parses
+    /// all of the per-process files together. A directory should only
+    /// contain the files from one prefix
+    #[arg(default_value_t)]
+    input: patharg::InputArg,
+
+    /// The format of the strace timestamps
+    #[arg(long, value_enum, default_value_t)]
+    timestamp_format: TimestampFormat,
+
+    /// The date (and optionally time) when the strace output was recorded.
+    /// Used for time-of-day and relative timestamps. Defaults to the time
+    /// the input file was created
+    #[arg(long, value_parser = parse_base_time)]
+    base_time: Option<jiff::Zoned>,
+
+    /// Parse strace output without a pid column (e.g. from strace without
+    /// `-f`), attributing every line to this pid
+    #[arg(long, conflicts_with = "pid_from_filename")]
+    pid: Option<systrument::Pid>,
+
+    /// Parse strace output without a pid column, taking the pid from the
+    /// input file's suffix (e.g. `trace.1234` from `strace -ff -o trace`)
+    #[arg(long)]
+    pid_from_filename: bool,
+}
+
+impl StraceInputArgs {
+    fn open(&self) -> miette::Result<Vec<StraceLines<'static>>> {
+        let path = match &self.input {
+            patharg::InputArg::Path(path) => path,
+            patharg::InputArg::Stdin => {
+                let input = self
+                    .input
+                    .open()
+                    .into_diagnostic()
+                    .wrap_err("failed to open stdin")?;
+                let parser = self.parser(None, self.pid)?;
+                return Ok(vec![StraceLines::new("<stdin>", parser, input.lines())]);
+            }
+        };
+
+        if path.is_dir() || !path.exists() {
+            // Parse each file written by `strace -ff -o <prefix>`, with the
+            // pid taken from each filename
+            let per_process_files = find_per_process_files(path)?;
+            if per_process_files.is_empty() {
+                if path.is_dir() {
+                    miette::bail!("no strace files found in directory {}", path.display());
+                } else {
+                    miette::bail!(
+                        "input path {} not found (and no strace files found with it as a prefix)",
+                        path.display()
+                    );
+                }
+            }
+
+            // Each file is opened lazily (see `ChunkedFileLines`), so we
+            // don't hit the open file limit with lots of processes
+            per_process_files
+                .into_iter()
+                .map(|(pid, path)| {
+                    let parser = self.parser(Some(&path), Some(pid))?;
+                    Ok(StraceLines::new(
+                        path.display().to_string(),
+                        parser,
+                        ChunkedFileLines::new(path),
+                    ))
+                })
+                .collect()
+        } else {
+            let pid = if self.pid_from_filename {
+                let pid = pid_from_filename(path).ok_or_else(|| {
+                    miette::miette!("failed to get pid from filename {}", path.display())
+                })?;
+                Some(pid)
+            } else {
+                self.pid
+            };
+
+            let input = self
+                .input
+                .open()
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to open input path {}", path.display()))?;
+            let parser = self.parser(Some(path), pid)?;
+            Ok(vec![StraceLines::new(
+                path.display().to_string(),
+                parser,
+                input.lines(),
+            )])
+        }
+    }
+
+    fn parser(
+        &self,
+        path: Option<&std::path::Path>,
+        pid: Option<systrument::Pid>,
+    ) -> miette::Result<systrument::strace::parser::LineParser> {
+        let base_time = match (&self.base_time, path) {
+            (Some(base_time), _) => base_time.clone(),
+            (None, Some(path)) => {
+                let metadata = std::fs::metadata(path)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("failed to get metadata for {}", path.display()))?;
+                let file_time = metadata
+                    .created()
+                    .or_else(|_| metadata.modified())
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("failed to get file time for {}", path.display()))?;
+                jiff::Zoned::try_from(file_time).into_diagnostic()?
+            }
+            (None, None) => jiff::Zoned::now(),
+        };
+
+        Ok(systrument::strace::parser::LineParser::new(
+            systrument::strace::parser::ParserOptions {
+                timestamp_format: self.timestamp_format.into(),
+                base_time: Some(base_time),
+                pid,
+            },
+        ))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, clap::ValueEnum)]
+enum TimestampFormat {
+    /// Detect the format of each timestamp
+    #[default]
+    Auto,
+
+    /// Seconds since the Unix epoch (`-ttt`)
+    Unix,
+
+    /// Time of day (`-t` or `-tt`)
+    TimeOfDay,
+
+    /// Seconds since the previous line (`-r`)
+    Relative,
+}
+
+impl From<TimestampFormat> for systrument::strace::parser::TimestampFormat {
+    fn from(format: TimestampFormat) -> Self {
+        match format {
+            TimestampFormat::Auto => Self::Auto,
+            TimestampFormat::Unix => Self::Unix,
+            TimestampFormat::TimeOfDay => Self::TimeOfDay,
+            TimestampFormat::Relative => Self::Relative,
+        }
+    }
+}
+
+/// Get the pid from a filename with a pid suffix, like the files written by
+/// `strace -ff -o prefix` (`prefix.1234`).
+fn pid_from_filename(path: &std::path::Path) -> Option<systrument::Pid> {
+    let suffix = path.extension()?.to_str()?;
+    suffix.parse().ok()
+}
+
+/// Find the files written by `strace -ff -o <prefix>`, either from the
+/// prefix itself or from the directory containing the files. Returns each
+/// path along with the pid from its filename, ordered by pid.
+fn find_per_process_files(
+    path: &std::path::Path,
+) -> miette::Result<Vec<(systrument::Pid, PathBuf)>> {
+    let (dir, prefix) = if path.is_dir() {
+        (path, None)
+    } else {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(std::path::Path::new("."));
+        (dir, path.file_name())
+    };
+
+    // A prefix in a directory that doesn't exist has no files
+    if prefix.is_some() && !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut files = vec![];
+    let entries = std::fs::read_dir(dir)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read directory {}", dir.display()))?;
+        let entry_path = entry.path();
+
+        if let Some(prefix) = prefix
+            && entry_path.file_stem() != Some(prefix)
+        {
+            continue;
+        }
+
+        let Some(pid) = pid_from_filename(&entry_path) else {
+            continue;
+        };
+        if !entry_path.is_file() {
+            continue;
+        }
+
+        files.push((pid, entry_path));
+    }
+
+    // Without a prefix, every file in the directory should come from the
+    // same trace, so unrelated traces don't get mixed together
+    if prefix.is_none() {
+        let prefixes: std::collections::BTreeSet<_> = files
+            .iter()
+            .filter_map(|(_, path)| path.file_stem())
+            .collect();
+        if prefixes.len() > 1 {
+            let prefixes = prefixes
+                .iter()
+                .map(|prefix| prefix.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(", ");
+            miette::bail!(
+                "found strace files with multiple prefixes in directory {} ({prefixes}), pass the prefix to use as the input path instead",
+                dir.display()
+            );
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Returns true if `$OTEL_SERVICE_NAME` or `$OTEL_RESOURCE_ATTRIBUTES` sets
+/// the service name, in which case it's used instead of our default.
+fn env_sets_service_name() -> bool {
+    if std::env::var_os("OTEL_SERVICE_NAME").is_some() {
+        return true;
+    }
+
+    std::env::var("OTEL_RESOURCE_ATTRIBUTES").is_ok_and(|attributes| {
+        attributes
+            .split(',')
+            .filter_map(|attribute| attribute.split_once('='))
+            .any(|(name, _)| name.trim() == "service.name")
+    })
+}
+
+fn parse_key_value(value: &str) -> Result<(String, String), String> {
+    let (name, value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got {value:?}"))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+fn parse_base_time(value: &str) -> Result<jiff::Zoned, jiff::Error> {
+    if let Ok(zoned) = value.parse::<jiff::Zoned>() {
+        return Ok(zoned);
+    }
+
+    if let Ok(timestamp) = value.parse::<jiff::Timestamp>() {
+        return Ok(timestamp.to_zoned(jiff::tz::TimeZone::system()));
+    }
+
+    let datetime = value.parse::<jiff::civil::DateTime>()?;
+    datetime.to_zoned(jiff::tz::TimeZone::system())
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -83,6 +681,9 @@ struct RecordArgs {
     #[arg(long)]
     otel: bool,
 
+    #[command(flatten)]
+    otel_args: OtelArgs,
+
     /// Write raw strace output to a file
     #[arg(short, long)]
     output_strace: Option<PathBuf>,
@@ -91,6 +692,14 @@ struct RecordArgs {
     #[arg(long)]
     output_perfetto: Option<PathBuf>,
 
+    /// Write a slice for each syscall to the Perfetto file
+    #[arg(long)]
+    perfetto_syscalls: bool,
+
+    /// Write counter tracks to the Perfetto file
+    #[arg(long)]
+    perfetto_counters: bool,
+
     /// The command to run
     #[arg(last = true)]
     command: Vec<std::ffi::OsString>,
@@ -108,6 +717,14 @@ fn main() -> miette::Result<ExitCode> {
             strace_to_otel(args)?;
             ExitCode::SUCCESS
         }
+        Command::Files(args) => {
+            files(args)?;
+            ExitCode::SUCCESS
+        }
+        Command::Deps(args) => {
+            deps(args)?;
+            ExitCode::SUCCESS
+        }
         Command::Record(args) => record(args)?,
     };
 
@@ -115,129 +732,48 @@ fn main() -> miette::Result<ExitCode> {
 }
 
 fn strace_to_perfetto(args: StraceToPerfettoArgs) -> miette::Result<()> {
-    let mut emitter = systrument::strace::analyzer::Analyzer::default();
-
-    let input = args
-        .input
-        .open()
-        .into_diagnostic()
-        .wrap_err_with(|| format!("failed to open input path {}", args.input))?;
+    let inputs = args.input.open()?;
     let output = args
         .output
         .create()
         .into_diagnostic()
-        .wrap_err_with(|| format!("failed to open output path {}", args.input))?;
+        .wrap_err_with(|| format!("failed to open output path {}", args.output))?;
     let mut perfetto_writer = systrument::perfetto::PerfettoOutput::new(
         output,
-        systrument::perfetto::PerfettoOutputOptions { logs: args.logs },
+        systrument::perfetto::PerfettoOutputOptions {
+            logs: args.logs,
+            syscalls: args.syscalls || !args.syscall_filter.is_empty(),
+            syscall_filter: args.syscall_filter,
+            counters: args.counters,
+        },
     );
 
-    let input_name = if args.input.is_stdin() {
-        "<stdin>".to_string()
-    } else {
-        args.input.to_string()
-    };
-
-    // Keep a queue of lines as we encounter them (we use a BTreeMap to order
-    // lines by timestamp)
-    let mut queued_lines = BTreeMap::new();
-
-    for (line_index, line) in input.lines().enumerate() {
-        let line = line.unwrap();
-
-        // Parse the line
-        let strace = systrument::strace::parser::parse_line(&line);
-        let strace = match strace {
-            Ok(strace) => strace,
-            Err(error) => {
-                let report = miette::Report::new(error).with_source_code(
-                    systrument::utils::OffsetSource::new_named(&input_name, line)
-                        .with_line_offset(line_index),
-                );
-                println!("{report:?}");
-                continue;
-            }
-        };
-
-        // Add it to the queue, ordered by timestamp
-        queued_lines.insert(strace.timestamp, (line_index, line));
-
-        // Emit any lines beyond the window size
-        while queued_lines.len() > WINDOW_SIZE {
-            let (line_index, line) = queued_lines.first_entry().unwrap().remove();
-            let strace = systrument::strace::parser::parse_line(&line).unwrap();
-
-            let event = match emitter.analyze(strace) {
-                Ok(event) => event,
-                Err(error) => {
-                    let report = miette::Report::new(error).with_source_code(
-                        systrument::utils::OffsetSource::new_named(&input_name, line)
-                            .with_line_offset(line_index),
-                    );
-                    println!("{report:?}");
-                    continue;
-                }
-            };
-
-            perfetto_writer
-                .output_event(event)
-                .expect("error writing Perfetto event");
-        }
-    }
-
-    // Handle remaining queued lines
-    for (line_index, line) in queued_lines.into_values() {
-        let strace = systrument::strace::parser::parse_line(&line).unwrap();
-
-        let event = match emitter.analyze(strace) {
-            Ok(event) => event,
-            Err(error) => {
-                let report = miette::Report::new(error).with_source_code(
-                    systrument::utils::OffsetSource::new_named(&input_name, line)
-                        .with_line_offset(line_index),
-                );
-                println!("{report:?}");
-                continue;
-            }
-        };
-
+    analyze_strace_lines(inputs, |event| {
         perfetto_writer
             .output_event(event)
             .expect("error writing Perfetto event");
-    }
+    })?;
+
+    perfetto_writer
+        .finish()
+        .expect("error finishing Perfetto output");
 
     Ok(())
 }
 
 fn strace_to_otel(args: StraceToOtelArgs) -> miette::Result<()> {
-    let otel_span_exporter = opentelemetry_otlp::SpanExporter::builder()
-        .with_http()
-        .build()
-        .into_diagnostic()
-        .wrap_err("failed to build OTLP span exporter")?;
-    let otel_trace_provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
-        .with_batch_exporter(otel_span_exporter)
-        .with_resource(
-            opentelemetry_sdk::Resource::builder()
-                .with_attribute(opentelemetry::KeyValue::new("service.name", "systrument"))
-                .build(),
-        )
-        .build();
+    let tokio_runtime = args.otel.tokio_runtime()?;
+    let _tokio_guard = tokio_runtime.as_ref().map(|runtime| runtime.enter());
+
+    let otel_span_exporter = args.otel.span_exporter()?;
+    let otel_trace_provider = args.otel.tracer_provider(otel_span_exporter);
     let otel_tracer = otel_trace_provider.tracer("systrument");
 
     let (otel_logger, otel_log_provider) = if args.logs {
-        let otel_log_exporter = opentelemetry_otlp::LogExporter::builder()
-            .with_http()
-            .build()
-            .into_diagnostic()
-            .wrap_err("failed to build OTLP log exporter")?;
+        let otel_log_exporter = args.otel.log_exporter()?;
         let otel_log_provider = opentelemetry_sdk::logs::SdkLoggerProvider::builder()
             .with_batch_exporter(otel_log_exporter)
-            .with_resource(
-                opentelemetry_sdk::Resource::builder()
-                    .with_attribute(opentelemetry::KeyValue::new("service.name", "systrument"))
-                    .build(),
-            )
+            .with_resource(args.otel.resource())
             .build();
         let otel_logger = otel_log_provider.logger("systrument");
         (Some(otel_logger), Some(otel_log_provider))
@@ -245,13 +781,7 @@ fn strace_to_otel(args: StraceToOtelArgs) -> miette::Result<()> {
         (None, None)
     };
 
-    let mut emitter = systrument::strace::analyzer::Analyzer::default();
-
-    let input = args
-        .input
-        .open()
-        .into_diagnostic()
-        .wrap_err_with(|| format!("failed to open input path {}", args.input))?;
+    let inputs = args.input.open()?;
 
     let relative_to = if args.relative_to_now {
         Some(jiff::Timestamp::now())
@@ -261,82 +791,14 @@ fn strace_to_otel(args: StraceToOtelArgs) -> miette::Result<()> {
     let mut otel_writer = systrument::otel::OtelOutput::new(
         otel_tracer,
         otel_logger,
-        systrument::otel::OtelOutputOptions { relative_to },
+        args.otel.output_options(relative_to),
     );
 
-    let input_name = if args.input.is_stdin() {
-        "<stdin>".to_string()
-    } else {
-        args.input.to_string()
-    };
-
-    // Keep a queue of lines as we encounter them (we use a BTreeMap to order
-    // lines by timestamp)
-    let mut queued_lines = BTreeMap::new();
-
-    for (line_index, line) in input.lines().enumerate() {
-        let line = line.unwrap();
-
-        // Parse the line
-        let strace = systrument::strace::parser::parse_line(&line);
-        let strace = match strace {
-            Ok(strace) => strace,
-            Err(error) => {
-                let report = miette::Report::new(error).with_source_code(
-                    systrument::utils::OffsetSource::new_named(&input_name, line)
-                        .with_line_offset(line_index),
-                );
-                println!("{report:?}");
-                continue;
-            }
-        };
-
-        // Add it to the queue, ordered by timestamp
-        queued_lines.insert(strace.timestamp, (line_index, line));
-
-        // Emit any lines beyond the window size
-        while queued_lines.len() > WINDOW_SIZE {
-            let (line_index, line) = queued_lines.first_entry().unwrap().remove();
-            let strace = systrument::strace::parser::parse_line(&line).unwrap();
-
-            let event = match emitter.analyze(strace) {
-                Ok(event) => event,
-                Err(error) => {
-                    let report = miette::Report::new(error).with_source_code(
-                        systrument::utils::OffsetSource::new_named(&input_name, line)
-                            .with_line_offset(line_index),
-                    );
-                    println!("{report:?}");
-                    continue;
-                }
-            };
-
-            otel_writer
-                .output_event(event)
-                .expect("error writing OTel event");
-        }
-    }
-
-    // Handle remaining queued lines
-    for (line_index, line) in queued_lines.into_values() {
-        let strace = systrument::strace::parser::parse_line(&line).unwrap();
-
-        let event = match emitter.analyze(strace) {
-            Ok(event) => event,
-            Err(error) => {
-                let report = miette::Report::new(error).with_source_code(
-                    systrument::utils::OffsetSource::new_named(&input_name, line)
-                        .with_line_offset(line_index),
-                );
-                println!("{report:?}");
-                continue;
-            }
-        };
-
+    analyze_strace_lines(inputs, |event| {
         otel_writer
             .output_event(event)
             .expect("error writing OTel event");
-    }
+    })?;
 
     // Shut down the writer
     drop(otel_writer);
@@ -356,11 +818,64 @@ fn strace_to_otel(args: StraceToOtelArgs) -> miette::Result<()> {
     Ok(())
 }
 
+fn files(args: FilesArgs) -> miette::Result<()> {
+    let inputs = args.input.open()?;
+    let output = args
+        .output
+        .create()
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to open output path {}", args.output))?;
+    let mut files_writer = systrument::files::FilesOutput::new(
+        output,
+        systrument::files::FilesOutputOptions {
+            format: args.format.into(),
+        },
+    );
+
+    analyze_strace_lines(inputs, |event| {
+        files_writer.output_event(event);
+    })?;
+
+    files_writer
+        .finish()
+        .map_err(|error| miette::miette!("{error}"))
+        .wrap_err("failed to write files report")?;
+
+    Ok(())
+}
+
+fn deps(args: DepsArgs) -> miette::Result<()> {
+    let inputs = args.input.open()?;
+    let output = args
+        .output
+        .create()
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to open output path {}", args.output))?;
+    let mut deps_writer = systrument::deps::DepsOutput::new(
+        output,
+        systrument::deps::DepsOutputOptions {
+            format: args.format.into(),
+            subtree: args.subtree,
+            include_system_files: args.include_system_files,
+        },
+    );
+
+    analyze_strace_lines(inputs, |event| {
+        deps_writer.output_event(event);
+    })?;
+
+    deps_writer
+        .finish()
+        .map_err(|error| miette::miette!("{error}"))
+        .wrap_err("failed to write deps")?;
+
+    Ok(())
+}
+
 fn record(args: RecordArgs) -> miette::Result<ExitCode> {
     let mut command = std::process::Command::new("strace");
     command
         .arg("-f")
-        .arg("--status=!unfinished")
         .arg("--string-limit=4096")
         .arg("--absolute-timestamps=unix,us")
         .arg("--syscall-times")
@@ -402,7 +917,12 @@ fn record(args: RecordArgs) -> miette::Result<ExitCode> {
                 })?;
             let writer = systrument::perfetto::PerfettoOutput::new(
                 output,
-                systrument::perfetto::PerfettoOutputOptions { logs: true },
+                systrument::perfetto::PerfettoOutputOptions {
+                    logs: true,
+                    syscalls: args.perfetto_syscalls,
+                    syscall_filter: vec![],
+                    counters: args.perfetto_counters,
+                },
             );
             Ok::<_, miette::Report>(writer)
         })
@@ -410,34 +930,22 @@ fn record(args: RecordArgs) -> miette::Result<ExitCode> {
 
     let mut otel_trace_provider = None;
     let mut otel_log_provider = None;
+    let tokio_runtime = if args.otel {
+        args.otel_args.tokio_runtime()?
+    } else {
+        None
+    };
+    let _tokio_guard = tokio_runtime.as_ref().map(|runtime| runtime.enter());
+
     let mut otel_writer = if args.otel {
-        let span_exporter = opentelemetry_otlp::SpanExporter::builder()
-            .with_http()
-            .build()
-            .into_diagnostic()
-            .wrap_err("failed to build OTLP span exporter")?;
-        let trace_provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
-            .with_batch_exporter(span_exporter)
-            .with_resource(
-                opentelemetry_sdk::Resource::builder()
-                    .with_attribute(opentelemetry::KeyValue::new("service.name", "systrument"))
-                    .build(),
-            )
-            .build();
+        let span_exporter = args.otel_args.span_exporter()?;
+        let trace_provider = args.otel_args.tracer_provider(span_exporter);
         let tracer = trace_provider.tracer("systrument");
 
-        let log_exporter = opentelemetry_otlp::LogExporter::builder()
-            .with_http()
-            .build()
-            .into_diagnostic()
-            .wrap_err("failed to build OTLP log exporter")?;
+        let log_exporter = args.otel_args.log_exporter()?;
         let log_provider = opentelemetry_sdk::logs::SdkLoggerProvider::builder()
             .with_batch_exporter(log_exporter)
-            .with_resource(
-                opentelemetry_sdk::Resource::builder()
-                    .with_attribute(opentelemetry::KeyValue::new("service.name", "systrument"))
-                    .build(),
-            )
+            .with_resource(args.otel_args.resource())
             .build();
         let logger = log_provider.logger("systrument");
 
@@ -447,7 +955,7 @@ fn record(args: RecordArgs) -> miette::Result<ExitCode> {
         Some(systrument::otel::OtelOutput::new(
             tracer,
             Some(logger),
-            systrument::otel::OtelOutputOptions { relative_to: None },
+            args.otel_args.output_options(None),
         ))
     } else {
         None
@@ -458,10 +966,7 @@ fn record(args: RecordArgs) -> miette::Result<ExitCode> {
         let output = std::fs::File::create(path)
             .into_diagnostic()
             .wrap_err_with(|| {
-                format!(
-                    "failed to create Perfetto output at path {}",
-                    path.display()
-                )
+                format!("failed to create strace output at path {}", path.display())
             })?;
         Some(output)
     } else {
@@ -476,84 +981,26 @@ fn record(args: RecordArgs) -> miette::Result<ExitCode> {
             .wrap_err_with(|| {
                 format!("failed to open FIFO at path {}", strace_pipe.path.display())
             })?;
-        let mut emitter = systrument::strace::analyzer::Analyzer::default();
-
-        // Keep a queue of lines as we encounter them (we use a BTreeMap to order
-        // lines by timestamp)
-        let mut queued_lines = BTreeMap::new();
 
+        // Write each line verbatim as it's read
         let strace_pipe = std::io::BufReader::new(strace_pipe);
-        for (line_index, line) in strace_pipe.lines().enumerate() {
-            let line = line.unwrap();
-
-            // Write the line verbatim
+        let lines = strace_pipe.lines().map(|line| {
+            let line = line?;
             if let Some(strace_writer) = &mut strace_writer {
-                writeln!(strace_writer, "{line}").unwrap();
-            }
-
-            // Parse the line
-            let strace = systrument::strace::parser::parse_line(&line);
-            let strace = match strace {
-                Ok(strace) => strace,
-                Err(error) => {
-                    let report = miette::Report::new(error).with_source_code(
-                        systrument::utils::OffsetSource::new_named("<strace>", line)
-                            .with_line_offset(line_index),
-                    );
-                    eprintln!("{report:?}");
-                    continue;
-                }
-            };
-
-            // Add it to the queue, ordered by timestamp
-            queued_lines.insert(strace.timestamp, (line_index, line));
-
-            // Emit any lines beyond the window size
-            while queued_lines.len() > WINDOW_SIZE {
-                let (line_index, line) = queued_lines.first_entry().unwrap().remove();
-                let strace = systrument::strace::parser::parse_line(&line).unwrap();
-
-                let event = match emitter.analyze(strace) {
-                    Ok(event) => event,
-                    Err(error) => {
-                        let report = miette::Report::new(error).with_source_code(
-                            systrument::utils::OffsetSource::new_named("<strace>", line)
-                                .with_line_offset(line_index),
-                        );
-                        println!("{report:?}");
-                        continue;
-                    }
-                };
-
-                if let Some(perfetto_writer) = &mut perfetto_writer {
-                    perfetto_writer
-                        .output_event(event.clone())
-                        .expect("error writing Perfetto event");
-                }
-                if let Some(otel_writer) = &mut otel_writer {
-                    otel_writer
-                        .output_event(event)
-                        .expect("error writing OTel event");
-                }
+                writeln!(strace_writer, "{line}")?;
             }
-        }
-
-        // Handle remaining queued lines
-        for (line_index, line) in queued_lines.into_values() {
-            let strace = systrument::strace::parser::parse_line(&line).unwrap();
-
-            let event = match emitter.analyze(strace) {
-                Ok(event) => event,
-                Err(error) => {
-                    let report = miette::Report::new(error).with_source_code(
-                        systrument::utils::OffsetSource::new_named("<strace>", line)
-                            .with_line_offset(line_index),
-                    );
-                    println!("{report:?}");
-                    continue;
-                }
-            };
-
+            Ok::<_, std::io::Error>(line)
+        });
+
+        let parser = systrument::strace::parser::LineParser::new(
+            systrument::strace::parser::ParserOptions {
+                timestamp_format: systrument::strace::parser::TimestampFormat::Unix,
+                base_time: None,
+                pid: None,
+            },
+        );
+        let inputs = vec![StraceLines::new("<strace>", parser, lines)];
+        analyze_strace_lines(inputs, |event| {
             if let Some(perfetto_writer) = &mut perfetto_writer {
                 perfetto_writer
                     .output_event(event.clone())
@@ -564,7 +1011,13 @@ fn record(args: RecordArgs) -> miette::Result<ExitCode> {
                     .output_event(event)
                     .expect("error writing OTel event");
             }
-        }
+        })?;
+    }
+
+    if let Some(perfetto_writer) = perfetto_writer {
+        perfetto_writer
+            .finish()
+            .expect("error finishing Perfetto output");
     }
 
     // Shut down the OTel writer
@@ -596,6 +1049,258 @@ fn record(args: RecordArgs) -> miette::Result<ExitCode> {
     }
 }
 
+self_cell::self_cell!(
+    /// A strace line along with its parsed form, so lines only get parsed
+    /// once while they're merged and re-ordered.
+    struct ParsedLine {
+        owner: String,
+
+        #[covariant]
+        dependent: StraceLine,
+    }
+);
+
+type StraceLine<'a> = systrument::strace::Line<'a>;
+
+/// A single source of strace lines, such as a file, stdin, or one of the
+/// per-process files written by `strace -ff`.
+struct StraceLines<'a> {
+    name: String,
+    parser: systrument::strace::parser::LineParser,
+    stitcher: systrument::strace::stitcher::Stitcher,
+    lines: Box<dyn Iterator<Item = std::io::Result<String>> + 'a>,
+    next_line_index: usize,
+}
+
+impl<'a> StraceLines<'a> {
+    fn new(
+        name: impl Into<String>,
+        parser: systrument::strace::parser::LineParser,
+        lines: impl Iterator<Item = std::io::Result<String>> + 'a,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            parser,
+            stitcher: systrument::strace::stitcher::Stitcher::default(),
+            lines: Box::new(lines),
+            next_line_index: 0,
+        }
+    }
+
+    /// Read and parse the next complete line, returning it along with its
+    /// line index. Unfinished syscalls are held back until they're resumed,
+    /// and lines that fail to parse are reported, then skipped.
+    fn next_line(&mut self) -> miette::Result<Option<(usize, ParsedLine)>> {
+        loop {
+            let Some(line) = self.lines.next() else {
+                return Ok(None);
+            };
+            let line = line
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to read line from {}", self.name))?;
+            let line_index = self.next_line_index;
+            self.next_line_index += 1;
+
+            // Parse the line
+            let parser = &mut self.parser;
+            let strace = match ParsedLine::try_new_or_recover(line, |line| parser.parse_line(line))
+            {
+                Ok(strace) => strace,
+                Err((line, error)) => {
+                    report_parse_error(&self.name, line_index, line, error);
+                    continue;
+                }
+            };
+
+            // Join unfinished syscalls with their resumed halves. Unfinished
+            // syscalls are held back until they're resumed
+            match self.stitcher.stitch(strace.borrow_dependent()) {
+                systrument::strace::stitcher::Stitched::Complete => {
+                    return Ok(Some((line_index, strace)));
+                }
+                systrument::strace::stitcher::Stitched::Unfinished => {}
+                systrument::strace::stitcher::Stitched::Resumed { line, timestamp } => {
+                    // The stitched line starts when the unfinished syscall
+                    // started
+                    let parser = &self.parser;
+                    match ParsedLine::try_new_or_recover(line, |line| {
+                        parser.parse_line_at(line, timestamp)
+                    }) {
+                        Ok(strace) => return Ok(Some((line_index, strace))),
+                        Err((line, error)) => {
+                            report_parse_error(&self.name, line_index, line, error);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The number of bytes [`ChunkedFileLines`] reads from a file each time it
+/// opens it.
+const FILE_CHUNK_SIZE: u64 = 16 * 1024;
+
+/// Reads lines from a file in chunks, only keeping the file open while
+/// reading each chunk. Used for the per-process files written by
+/// `strace -ff`, where there can be more files than we can keep open at once.
+struct ChunkedFileLines {
+    path: PathBuf,
+    offset: u64,
+    lines: std::collections::VecDeque<String>,
+    done: bool,
+}
+
+impl ChunkedFileLines {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            offset: 0,
+            lines: std::collections::VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Open the file and read lines starting from the current offset, until
+    /// at least [`FILE_CHUNK_SIZE`] bytes have been read or the end of the
+    /// file is reached.
+    fn read_chunk(&mut self) -> std::io::Result<()> {
+        let mut file = std::fs::File::open(&self.path)?;
+        file.seek(std::io::SeekFrom::Start(self.offset))?;
+        let mut file = std::io::BufReader::new(file);
+
+        let mut chunk_bytes = 0;
+        while chunk_bytes < FILE_CHUNK_SIZE {
+            let mut line = String::new();
+            let line_bytes = file.read_line(&mut line)?;
+            if line_bytes == 0 {
+                self.done = true;
+                break;
+            }
+
+            self.offset += line_bytes as u64;
+            chunk_bytes += line_bytes as u64;
+
+            // Strip the line ending, like `BufRead::lines`
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            self.lines.push_back(line);
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for ChunkedFileLines {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.lines.is_empty()
+            && !self.done
+            && let Err(error) = self.read_chunk()
+        {
+            self.done = true;
+            return Some(Err(error));
+        }
+
+        self.lines.pop_front().map(Ok)
+    }
+}
+
+/// Parse, stitch, and analyze strace lines, calling `output_event` with each
+/// analyzed event. Lines from each input are merged by timestamp (see
+/// [`systrument::strace::Line::analyze_at`]), and re-ordered within a
+/// window of [`WINDOW_SIZE`] lines. Parse errors are reported, then skipped.
+fn analyze_strace_lines(
+    mut inputs: Vec<StraceLines>,
+    mut output_event: impl FnMut(systrument::event::Event),
+) -> miette::Result<()> {
+    let mut analyzer = systrument::strace::analyzer::Analyzer::default();
+
+    // Keep the next line from each input, so we can always take the
+    // earliest line across all inputs. The heap orders the inputs by their
+    // next line, and the lines themselves are kept by input index
+    let mut next_lines = vec![];
+    let mut next_line_order = std::collections::BinaryHeap::new();
+    for (input_index, input) in inputs.iter_mut().enumerate() {
+        let next_line = input.next_line()?;
+        if let Some((line_index, strace)) = &next_line {
+            let timestamp = strace.borrow_dependent().analyze_at();
+            next_line_order.push(std::cmp::Reverse((timestamp, input_index, *line_index)));
+        }
+        next_lines.push(next_line.map(|(_, strace)| strace));
+    }
+
+    // Keep a queue of lines as we encounter them (we use a BTreeMap to order
+    // lines by timestamp, then by input and line index). Lines are ordered
+    // by when they should be analyzed, so `wait` syscalls come after the
+    // exit of the child they reaped
+    let mut queued_lines = BTreeMap::new();
+
+    let mut emit_line = |input: &StraceLines, line_index: usize, strace: ParsedLine| {
+        let event = analyzer.analyze(strace.borrow_dependent().clone());
+        match event {
+            Ok(event) => {
+                output_event(event);
+            }
+            Err(error) => {
+                report_parse_error(
+                    &input.name,
+                    line_index,
+                    strace.borrow_owner().clone(),
+                    error,
+                );
+            }
+        }
+    };
+
+    while let Some(std::cmp::Reverse((timestamp, input_index, line_index))) = next_line_order.pop()
+    {
+        let strace = next_lines[input_index]
+            .take()
+            .expect("next line not found for input");
+
+        let next_line = inputs[input_index].next_line()?;
+        if let Some((line_index, next_strace)) = &next_line {
+            let timestamp = next_strace.borrow_dependent().analyze_at();
+            next_line_order.push(std::cmp::Reverse((timestamp, input_index, *line_index)));
+        }
+        next_lines[input_index] = next_line.map(|(_, strace)| strace);
+
+        // Add it to the queue, ordered by timestamp
+        queued_lines.insert((timestamp, input_index, line_index), strace);
+
+        // Emit any lines beyond the window size
+        while queued_lines.len() > WINDOW_SIZE {
+            let ((_, input_index, line_index), strace) = queued_lines.pop_first().unwrap();
+            emit_line(&inputs[input_index], line_index, strace);
+        }
+    }
+
+    // Handle remaining queued lines
+    for ((_, input_index, line_index), strace) in queued_lines {
+        emit_line(&inputs[input_index], line_index, strace);
+    }
+
+    Ok(())
+}
+
+fn report_parse_error(
+    input_name: &str,
+    line_index: usize,
+    line: String,
+    error: systrument::strace::parser::StraceParseError,
+) {
+    let report = miette::Report::new(error).with_source_code(
+        systrument::utils::OffsetSource::new_named(input_name, line).with_line_offset(line_index),
+    );
+    eprintln!("{report:?}");
+}
+
 #[cfg(unix)]
 fn create_pipe() -> miette::Result<TempPipe> {
     let id = uuid::Uuid::new_v4();
@@ -622,3 +1327,167 @@ impl Drop for TempPipe {
         let _ = std::fs::remove_file(&self.path);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    struct TestDir {
+        path: PathBuf,
+    }
+
+    impl TestDir {
+        fn new() -> Self {
+            let id = uuid::Uuid::new_v4();
+            let path = std::env::temp_dir().join(format!("systrument-test-{id}"));
+            std::fs::create_dir(&path).unwrap();
+            Self { path }
+        }
+
+        fn write(&self, name: &str, contents: &str) -> PathBuf {
+            let path = self.path.join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn input_args(path: &Path) -> StraceInputArgs {
+        StraceInputArgs {
+            input: patharg::InputArg::Path(path.to_owned()),
+            timestamp_format: TimestampFormat::Auto,
+            base_time: None,
+            pid: None,
+            pid_from_filename: false,
+        }
+    }
+
+    #[test]
+    fn test_pid_from_filename() {
+        assert_eq!(pid_from_filename(Path::new("trace.1234")), Some(1234));
+        assert_eq!(pid_from_filename(Path::new("dir/trace.out.56")), Some(56));
+        assert_eq!(pid_from_filename(Path::new("trace.txt")), None);
+        assert_eq!(pid_from_filename(Path::new("trace")), None);
+    }
+
+    #[test]
+    fn test_find_per_process_files() {
+        let dir = TestDir::new();
+        dir.write("trace.102", "");
+        dir.write("trace.100", "");
+        dir.write("trace.101", "");
+        dir.write("trace.log", "");
+        dir.write("other.103", "");
+        std::fs::create_dir(dir.path.join("trace.104")).unwrap();
+
+        let files = find_per_process_files(&dir.path.join("trace")).unwrap();
+        assert_eq!(
+            files,
+            [
+                (100, dir.path.join("trace.100")),
+                (101, dir.path.join("trace.101")),
+                (102, dir.path.join("trace.102")),
+            ]
+        );
+
+        // Files from other traces aren't mixed in with a directory
+        let Err(error) = find_per_process_files(&dir.path) else {
+            panic!("expected an error for a directory with multiple prefixes");
+        };
+        assert!(error.to_string().contains("(other, trace)"), "{error}");
+
+        std::fs::remove_file(dir.path.join("other.103")).unwrap();
+        let files = find_per_process_files(&dir.path).unwrap();
+        let pids: Vec<_> = files.iter().map(|(pid, _)| *pid).collect();
+        assert_eq!(pids, [100, 101, 102]);
+
+        let files = find_per_process_files(&dir.path.join("nope").join("trace")).unwrap();
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_missing_input_path() {
+        let dir = TestDir::new();
+        dir.write("trace.100", "");
+
+        let Err(error) = input_args(&dir.path.join("tarce")).open() else {
+            panic!("expected an error for a missing input path");
+        };
+        assert!(error.to_string().contains("not found"), "{error}");
+    }
+
+    #[test]
+    fn test_analyze_interleaved_per_process_files() {
+        let dir = TestDir::new();
+        dir.write(
+            "trace.100",
+            r#"1757048541.000000 execve("/bin/sh", ["sh"], []) = 0 <0.000100>
+1757048541.000300 getpid() = 100 <0.000010>
+1757048541.000600 getpid() = 100 <0.000010>
+"#,
+        );
+        dir.write(
+            "trace.101",
+            r#"1757048541.000100 execve("/bin/true", ["true"], []) = 0 <0.000100>
+1757048541.000400 getpid() = 101 <0.000010>
+"#,
+        );
+        dir.write(
+            "trace.102",
+            r#"1757048541.000200 execve("/bin/false", ["false"], []) = 0 <0.000100>
+1757048541.000500 getpid() = 102 <0.000010>
+1757048541.000700 getpid() = 102 <0.000010>
+"#,
+        );
+
+        let inputs = input_args(&dir.path.join("trace")).open().unwrap();
+        assert_eq!(inputs.len(), 3);
+
+        let mut events = vec![];
+        analyze_strace_lines(inputs, |event| {
+            events.push((event.pid, event.timestamp.as_microsecond() % 1_000_000));
+        })
+        .unwrap();
+
+        assert_eq!(
+            events,
+            [
+                (100, 0),
+                (101, 100),
+                (102, 200),
+                (100, 300),
+                (101, 400),
+                (102, 500),
+                (100, 600),
+                (102, 700),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunked_file_lines() {
+        let dir = TestDir::new();
+
+        let mut contents = String::new();
+        for index in 0..5_000 {
+            let line_ending = if index % 2 == 0 { "\n" } else { "\r\n" };
+            contents.push_str(&format!("line {index}{line_ending}"));
+        }
+        contents.push_str("last line without a newline");
+        assert!(contents.len() as u64 > FILE_CHUNK_SIZE * 2);
+
+        let path = dir.write("lines", &contents);
+        let lines: Vec<_> = ChunkedFileLines::new(path)
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        let expected: Vec<_> = contents.lines().collect();
+        assert_eq!(lines, expected);
+    }
+}
@@ -13,6 +13,19 @@ const ROOT_SPAN_NAME: &str = "processes";
 #[derive(Debug, Default)]
 pub struct OtelOutputOptions {
     pub relative_to: Option<jiff::Timestamp>,
+
+    /// Add each syscall as an event on the span of the process that owns it.
+    pub syscall_events: bool,
+
+    /// The maximum number of syscall events to add to each span. Later
+    /// syscalls are dropped, and counted with the `dropped_syscall_events`
+    /// attribute.
+    pub max_syscall_events_per_span: Option<usize>,
+
+    /// Use the OpenTelemetry semantic conventions for process attributes
+    /// (e.g. `process.pid` instead of `pid`), and set the span status to
+    /// error when a process fails.
+    pub semantic_conventions: bool,
 }
 
 pub struct OtelOutput<T, L>
@@ -25,6 +38,7 @@ where
     logger: Option<L>,
     root_span: std::cell::OnceCell<opentelemetry_sdk::trace::Span>,
     process_spans: HashMap<crate::Pid, opentelemetry_sdk::trace::Span>,
+    syscall_event_counts: HashMap<crate::Pid, usize>,
     first_event_timestamp: Option<jiff::Timestamp>,
     last_event_timestamp: Option<jiff::Timestamp>,
 }
@@ -40,6 +54,7 @@ where
             logger,
             tracer,
             process_spans: HashMap::new(),
+            syscall_event_counts: HashMap::new(),
             root_span: OnceCell::new(),
             first_event_timestamp: None,
             last_event_timestamp: None,
@@ -48,10 +63,13 @@ where
 
     pub fn output_event(&mut self, event: Event) -> Result<(), Box<dyn std::error::Error>> {
         self.first_event_timestamp = Some(self.first_event_timestamp.unwrap_or(event.timestamp));
-        self.last_event_timestamp = Some(event.timestamp);
+        // Stitched events use the time their syscall started, so events can
+        // arrive out of order
+        self.last_event_timestamp = self.last_event_timestamp.max(Some(event.timestamp));
 
         let adjusted_timestamp = self.adjust_timestamp(event.timestamp);
 
+        let mut file_access_event = None;
         match event.kind {
             crate::event::EventKind::ExecProcess(exec_process_event) => {
                 let command_name = exec_process_event.exec.command_name().map_or_else(
@@ -67,48 +85,63 @@ where
                     .unwrap_or_else(|| self.root_span(event.timestamp).span_context().clone());
                 let cx =
                     opentelemetry::Context::new().with_remote_span_context(parent_span_context);
-                let attributes =
-                    std::iter::once(opentelemetry::KeyValue::new("pid", i64::from(event.pid)))
-                        .chain(event.parent_pid.map(|parent_pid| {
-                            opentelemetry::KeyValue::new("parent_pid", i64::from(parent_pid))
-                        }))
-                        .chain(event.owner_pid.map(|owner_pid| {
-                            opentelemetry::KeyValue::new("owner_pid", i64::from(owner_pid))
-                        }))
-                        .chain(exec_process_event.exec.command_name().into_iter().map(
-                            |command_name| {
-                                opentelemetry::KeyValue::new(
-                                    "command_name",
-                                    command_name.to_str_lossy().into_owned(),
-                                )
-                            },
-                        ))
-                        .chain(exec_process_event.exec.command.iter().map(|command| {
+                let key = self.attribute_keys();
+                let attributes = std::iter::once(opentelemetry::KeyValue::new(
+                    key("pid", "process.pid"),
+                    i64::from(event.pid),
+                ))
+                .chain(event.parent_pid.map(|parent_pid| {
+                    opentelemetry::KeyValue::new(
+                        key("parent_pid", "process.parent_pid"),
+                        i64::from(parent_pid),
+                    )
+                }))
+                .chain(event.owner_pid.map(|owner_pid| {
+                    opentelemetry::KeyValue::new("owner_pid", i64::from(owner_pid))
+                }))
+                .chain(
+                    exec_process_event
+                        .exec
+                        .command_name()
+                        .into_iter()
+                        .map(|command_name| {
                             opentelemetry::KeyValue::new(
-                                "command",
-                                command.to_str_lossy().into_owned(),
+                                key("command_name", "process.executable.name"),
+                                command_name.to_str_lossy().into_owned(),
                             )
-                        }))
-                        .chain(exec_process_event.exec.args.iter().map(|args| {
+                        }),
+                )
+                .chain(exec_process_event.exec.command.iter().map(|command| {
+                    opentelemetry::KeyValue::new(
+                        key("command", "process.executable.path"),
+                        command.to_str_lossy().into_owned(),
+                    )
+                }))
+                .chain(exec_process_event.exec.args.iter().map(|args| {
+                    opentelemetry::KeyValue::new(
+                        key("args", "process.command_args"),
+                        opentelemetry::Value::Array(opentelemetry::Array::String(
+                            args.iter()
+                                .map(|arg| arg.to_str_lossy().into_owned().into())
+                                .collect(),
+                        )),
+                    )
+                }))
+                .chain(
+                    exec_process_event
+                        .exec
+                        .env
+                        .iter()
+                        .flatten()
+                        .map(|(name, value)| {
                             opentelemetry::KeyValue::new(
-                                "args",
-                                opentelemetry::Value::Array(opentelemetry::Array::String(
-                                    args.iter()
-                                        .map(|arg| arg.to_str_lossy().into_owned().into())
-                                        .collect(),
-                                )),
+                                format!("{}.{name}", key("env", "process.environment_variable")),
+                                opentelemetry::Value::String(
+                                    value.to_str_lossy().into_owned().into(),
+                                ),
                             )
-                        }))
-                        .chain(exec_process_event.exec.env.iter().flatten().map(
-                            |(name, value)| {
-                                opentelemetry::KeyValue::new(
-                                    format!("env.{name}"),
-                                    opentelemetry::Value::String(
-                                        value.to_str_lossy().into_owned().into(),
-                                    ),
-                                )
-                            },
-                        ));
+                        }),
+                );
                 let span = self
                     .tracer
                     .span_builder(command_name)
@@ -118,22 +151,47 @@ where
                 let prev_span = self.process_spans.insert(event.pid, span);
 
                 if let Some(mut prev_span) = prev_span {
+                    self.add_dropped_syscall_events(event.pid, &mut prev_span);
                     prev_span.set_attribute(opentelemetry::KeyValue::new("re_exec", true));
                     prev_span.end_with_timestamp(adjusted_timestamp.into());
                 }
             }
+            // A thread exiting doesn't stop its process
+            crate::event::EventKind::StopProcess(stop_process_event)
+                if stop_process_event.thread => {}
             crate::event::EventKind::StopProcess(stop_process_event) => {
-                if let Some(mut span) = self.process_spans.remove(&event.pid) {
+                // The process could be stopped from any of its threads
+                // (with `exit_group`)
+                if let Some(mut span) = self.process_spans.remove(&event.tgid) {
+                    self.add_dropped_syscall_events(event.tgid, &mut span);
                     match stop_process_event.stopped {
                         crate::event::ProcessStoppedReason::Exited { code } => {
                             if let Some(code) = code {
+                                let key = self.attribute_keys();
                                 span.set_attributes([
-                                    opentelemetry::KeyValue::new("exit_code", i64::from(code)),
+                                    opentelemetry::KeyValue::new(
+                                        key("exit_code", "process.exit.code"),
+                                        i64::from(code),
+                                    ),
                                     opentelemetry::KeyValue::new("exit_ok", code == 0),
                                 ]);
+
+                                if self.options.semantic_conventions && code != 0 {
+                                    span.set_status(opentelemetry::trace::Status::error(format!(
+                                        "exited with code {code}"
+                                    )));
+                                }
                             }
                         }
                         crate::event::ProcessStoppedReason::Killed { signal } => {
+                            if self.options.semantic_conventions {
+                                let description = match &signal {
+                                    Some(signal) => format!("killed by {signal}"),
+                                    None => "killed".to_string(),
+                                };
+                                span.set_status(opentelemetry::trace::Status::error(description));
+                            }
+
                             span.set_attributes(
                                 std::iter::once(opentelemetry::KeyValue::new("exit_ok", false))
                                     .chain(signal.map(|signal| {
@@ -146,9 +204,172 @@ where
                     span.end_with_timestamp(adjusted_timestamp.into());
                 }
             }
+            crate::event::EventKind::FileAccess(file_access) => {
+                file_access_event = Some(file_access);
+            }
+            crate::event::EventKind::Signal(signal_event) => {
+                // Show the signal on the span of the process that owns it,
+                // linked to the span of the process that sent it
+                let span_pid = self.owning_span_pid(event.pid, event.owner_pid);
+                let sender_span_context = signal_event
+                    .sent_by
+                    .as_ref()
+                    .and_then(|sent_by| self.owning_span_pid(sent_by.pid, sent_by.owner_pid))
+                    .filter(|&sender_span_pid| Some(sender_span_pid) != span_pid)
+                    .and_then(|sender_span_pid| self.process_spans.get(&sender_span_pid))
+                    .map(|sender_span| sender_span.span_context().clone());
+                if let Some(span) = span_pid.and_then(|pid| self.process_spans.get_mut(&pid)) {
+                    if let Some(sender_span_context) = sender_span_context {
+                        span.add_link(
+                            sender_span_context,
+                            vec![opentelemetry::KeyValue::new(
+                                "signal",
+                                signal_event.signal.clone(),
+                            )],
+                        );
+                    }
+
+                    let attributes = [
+                        opentelemetry::KeyValue::new("signal", signal_event.signal.clone()),
+                        opentelemetry::KeyValue::new("pid", i64::from(event.pid)),
+                    ]
+                    .into_iter()
+                    .chain(
+                        signal_event
+                            .code
+                            .map(|code| opentelemetry::KeyValue::new("signal_code", code)),
+                    )
+                    .chain(signal_event.sender_pid.map(|sender_pid| {
+                        opentelemetry::KeyValue::new("sender_pid", i64::from(sender_pid))
+                    }))
+                    .chain(
+                        signal_event
+                            .status
+                            .map(|status| opentelemetry::KeyValue::new("signal_status", status)),
+                    )
+                    .chain(signal_event.sent_by.into_iter().flat_map(|sent_by| {
+                        [
+                            opentelemetry::KeyValue::new("sent_by_pid", i64::from(sent_by.pid)),
+                            opentelemetry::KeyValue::new("sent_by_syscall", sent_by.syscall),
+                        ]
+                    }))
+                    .collect();
+                    span.add_event_with_timestamp(
+                        signal_event.signal,
+                        adjusted_timestamp.into(),
+                        attributes,
+                    );
+                }
+            }
+            crate::event::EventKind::SuspendProcess(suspend_process_event) => {
+                if let Some(span) = self.process_spans.get_mut(&event.pid) {
+                    span.add_event_with_timestamp(
+                        "suspended",
+                        adjusted_timestamp.into(),
+                        vec![opentelemetry::KeyValue::new(
+                            "signal",
+                            suspend_process_event.signal,
+                        )],
+                    );
+                }
+            }
+            crate::event::EventKind::ContinueProcess(continue_process_event) => {
+                if let Some(span) = self.process_spans.get_mut(&event.pid) {
+                    let suspended_seconds = event
+                        .timestamp
+                        .duration_since(continue_process_event.suspended_at)
+                        .as_secs_f64();
+                    let attributes = std::iter::once(opentelemetry::KeyValue::new(
+                        "suspended_seconds",
+                        suspended_seconds,
+                    ))
+                    .chain(continue_process_event.signal.sender_pid.map(|sender_pid| {
+                        opentelemetry::KeyValue::new("sender_pid", i64::from(sender_pid))
+                    }))
+                    .collect();
+                    span.add_event_with_timestamp(
+                        "continued",
+                        adjusted_timestamp.into(),
+                        attributes,
+                    );
+                }
+            }
+            crate::event::EventKind::ReapProcess(reap_process_event) => {
+                // Show the reaped child on the span of the process that
+                // owns it
+                let span_pid = self.owning_span_pid(event.pid, event.owner_pid);
+                let reaped_at = self.adjust_timestamp(reap_process_event.reaped_at);
+                if let Some(span) = span_pid.and_then(|pid| self.process_spans.get_mut(&pid)) {
+                    let attributes = [
+                        opentelemetry::KeyValue::new(
+                            "child_pid",
+                            i64::from(reap_process_event.child_pid),
+                        ),
+                        opentelemetry::KeyValue::new(
+                            "wait_seconds",
+                            reap_process_event.wait_duration.as_secs_f64(),
+                        ),
+                        opentelemetry::KeyValue::new("orphan", reap_process_event.orphan),
+                    ]
+                    .into_iter()
+                    .chain(reap_process_event.exited_at.map(|exited_at| {
+                        let zombie_seconds = reap_process_event
+                            .reaped_at
+                            .duration_since(exited_at)
+                            .as_secs_f64();
+                        opentelemetry::KeyValue::new("zombie_seconds", zombie_seconds)
+                    }))
+                    .collect();
+                    span.add_event_with_timestamp("reaped", reaped_at.into(), attributes);
+                }
+            }
             crate::event::EventKind::ForkProcess(_) | crate::event::EventKind::Log => {}
         };
 
+        if self.options.syscall_events
+            && let crate::strace::Event::Syscall(syscall) = &event.strace.event
+        {
+            // Add the syscall to the span of the process that owns it
+            if let Some(span_pid) = self.owning_span_pid(event.pid, event.owner_pid) {
+                let syscall_event_count = self.syscall_event_counts.entry(span_pid).or_default();
+                *syscall_event_count += 1;
+
+                let under_limit = self
+                    .options
+                    .max_syscall_events_per_span
+                    .is_none_or(|max| *syscall_event_count <= max);
+                if under_limit && let Some(span) = self.process_spans.get_mut(&span_pid) {
+                    let mut attributes = vec![
+                        opentelemetry::KeyValue::new("pid", i64::from(event.pid)),
+                        opentelemetry::KeyValue::new("args", syscall.args_string.value.to_string()),
+                        opentelemetry::KeyValue::new(
+                            "result",
+                            syscall.result_string.value.to_string(),
+                        ),
+                        opentelemetry::KeyValue::new(
+                            "duration_seconds",
+                            syscall.duration.as_secs_f64(),
+                        ),
+                    ];
+                    if let Ok(result) = syscall.result()
+                        && result.is_failed()
+                    {
+                        attributes.push(opentelemetry::KeyValue::new("failed", true));
+                        if let Some(name) = result.error.and_then(|error| error.name) {
+                            attributes
+                                .push(opentelemetry::KeyValue::new("errno", name.to_string()));
+                        }
+                    }
+
+                    span.add_event_with_timestamp(
+                        syscall.name.to_string(),
+                        adjusted_timestamp.into(),
+                        attributes,
+                    );
+                }
+            }
+        }
+
         if self.logger.is_some() {
             let span_context = self
                 .process_spans
@@ -163,12 +384,41 @@ where
             log.set_trace_context(span_context.trace_id(), span_context.span_id(), None);
 
             log.add_attribute("pid", event.pid);
+            if event.tgid != event.pid {
+                log.add_attribute("tgid", event.tgid);
+            }
             if let Some(parent_pid) = event.parent_pid {
                 log.add_attribute("parent_pid", parent_pid);
             }
             if let Some(owner_pid) = event.owner_pid {
                 log.add_attribute("owner_pid", owner_pid);
             }
+            if let Some(file_access_event) = file_access_event {
+                log.add_attribute(
+                    "file_paths",
+                    opentelemetry::logs::AnyValue::ListAny(Box::new(
+                        file_access_event
+                            .accesses
+                            .iter()
+                            .map(|access| access.path.to_str_lossy().into_owned().into())
+                            .collect(),
+                    )),
+                );
+                log.add_attribute(
+                    "file_modes",
+                    opentelemetry::logs::AnyValue::ListAny(Box::new(
+                        file_access_event
+                            .accesses
+                            .iter()
+                            .map(|access| access.mode.as_str().into())
+                            .collect(),
+                    )),
+                );
+                if let Some(flags) = file_access_event.flags {
+                    log.add_attribute("file_flags", flags);
+                }
+                log.add_attribute("file_success", file_access_event.success);
+            }
 
             match event.strace.event {
                 crate::strace::Event::Syscall(syscall) => {
@@ -182,11 +432,44 @@ where
                     log.add_attribute("syscall", syscall.name.to_string());
                     log.add_attribute("args", syscall.args_string.value.to_string());
                     log.add_attribute("result", syscall.result_string.value.to_string());
+                    if let Ok(result) = syscall.result() {
+                        add_syscall_result_attributes(&mut log, &result);
+                    }
+                }
+                crate::strace::Event::Unfinished(unfinished) => {
+                    log.set_body(
+                        format!(
+                            "{}({} <unfinished ...>",
+                            unfinished.name, unfinished.args_string.value
+                        )
+                        .into(),
+                    );
+                    log.add_attribute("syscall", unfinished.name.to_string());
+                    log.add_attribute("args", unfinished.args_string.value.to_string());
+                }
+                crate::strace::Event::Resumed(resumed) => {
+                    log.set_body(
+                        format!(
+                            "<... {} resumed>{}) = {}",
+                            resumed.name, resumed.args_string.value, resumed.result_string.value
+                        )
+                        .into(),
+                    );
+                    log.add_attribute("syscall", resumed.name.to_string());
+                    log.add_attribute("args", resumed.args_string.value.to_string());
+                    log.add_attribute("result", resumed.result_string.value.to_string());
+                    if let Ok(result) = resumed.result() {
+                        add_syscall_result_attributes(&mut log, &result);
+                    }
                 }
                 crate::strace::Event::Signal { signal } => {
                     log.set_body(format!("--- {signal} ---").into());
                     log.add_attribute("signal", signal.to_string());
                 }
+                crate::strace::Event::GroupStop { signal } => {
+                    log.set_body(format!("--- stopped by {signal} ---").into());
+                    log.add_attribute("signal", signal.to_string());
+                }
                 crate::strace::Event::Exited(exited_event) => {
                     log.set_body(
                         format!("+++ exited with {} +++", exited_event.code_string.value).into(),
@@ -201,6 +484,17 @@ where
                     log.set_body(format!("+++ killed by {} +++", signal_string.value).into());
                     log.add_attribute("signal", signal_string.value.to_string());
                 }
+                crate::strace::Event::Superseded { thread_pid } => match thread_pid {
+                    Some(thread_pid) => {
+                        log.set_body(
+                            format!("+++ superseded by execve in pid {thread_pid} +++").into(),
+                        );
+                        log.add_attribute("thread_pid", thread_pid);
+                    }
+                    None => {
+                        log.set_body("+++ superseded by execve +++".into());
+                    }
+                },
             }
 
             logger.emit(log);
@@ -209,6 +503,53 @@ where
         Ok(())
     }
 
+    /// Get a function to pick between the custom key for a process attribute
+    /// and its key in the OpenTelemetry semantic conventions.
+    fn attribute_keys(&self) -> impl Fn(&'static str, &'static str) -> &'static str + use<T, L> {
+        let semantic_conventions = self.options.semantic_conventions;
+        move |custom_key, semantic_key| {
+            if semantic_conventions {
+                semantic_key
+            } else {
+                custom_key
+            }
+        }
+    }
+
+    /// Get the pid of the span for a process: the process's own span if it's
+    /// exec'd, otherwise its owner's span (if it has one).
+    fn owning_span_pid(
+        &self,
+        pid: crate::Pid,
+        owner_pid: Option<crate::Pid>,
+    ) -> Option<crate::Pid> {
+        [Some(pid), owner_pid]
+            .into_iter()
+            .flatten()
+            .find(|pid| self.process_spans.contains_key(pid))
+    }
+
+    /// Record how many syscall events were dropped from a span because of
+    /// the per-span limit, before the span ends.
+    fn add_dropped_syscall_events(
+        &mut self,
+        pid: crate::Pid,
+        span: &mut opentelemetry_sdk::trace::Span,
+    ) {
+        let syscall_event_count = self.syscall_event_counts.remove(&pid).unwrap_or(0);
+        let Some(max) = self.options.max_syscall_events_per_span else {
+            return;
+        };
+
+        let dropped = syscall_event_count.saturating_sub(max);
+        if dropped > 0 {
+            span.set_attribute(opentelemetry::KeyValue::new(
+                "dropped_syscall_events",
+                i64::try_from(dropped).unwrap_or(i64::MAX),
+            ));
+        }
+    }
+
     fn adjust_timestamp(&self, event_timestamp: jiff::Timestamp) -> jiff::Timestamp {
         let Some(relative_to) = self.options.relative_to else {
             return event_timestamp;
@@ -237,12 +578,49 @@ where
     }
 }
 
+fn add_syscall_result_attributes(
+    log: &mut opentelemetry_sdk::logs::SdkLogRecord,
+    result: &crate::strace::SyscallResult,
+) {
+    if let Some(error) = &result.error {
+        if let Some(name) = error.name {
+            log.add_attribute("errno", name.to_string());
+        }
+        if let Some(number) = error.number {
+            log.add_attribute("errno_number", number);
+        }
+    }
+    if let Some(message) = result.message {
+        log.add_attribute("result_message", message.to_string());
+    }
+    if result.is_failed() {
+        log.add_attribute("failed", true);
+        log.set_severity_number(opentelemetry::logs::Severity::Warn);
+    }
+}
+
 impl<T, L> Drop for OtelOutput<T, L>
 where
     T: opentelemetry::trace::Tracer<Span = opentelemetry_sdk::trace::Span>,
     L: opentelemetry::logs::Logger<LogRecord = opentelemetry_sdk::logs::SdkLogRecord>,
 {
     fn drop(&mut self) {
+        // End the spans of any processes that were still running when the
+        // trace ended
+        let last_event_timestamp = self
+            .last_event_timestamp
+            .map(|timestamp| self.adjust_timestamp(timestamp));
+        let process_spans = std::mem::take(&mut self.process_spans);
+        for (pid, mut span) in process_spans {
+            self.add_dropped_syscall_events(pid, &mut span);
+            span.set_attribute(opentelemetry::KeyValue::new("truncated", true));
+            if let Some(last_event_timestamp) = last_event_timestamp {
+                span.end_with_timestamp(last_event_timestamp.into());
+            } else {
+                span.end();
+            }
+        }
+
         if let Some(mut root_span) = self.root_span.take() {
             if let Some(last_event_timestamp) = self.last_event_timestamp {
                 let adjusted_timestamp = self.adjust_timestamp(last_event_timestamp);
@@ -1,35 +1,65 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use bstr::ByteVec as _;
+use bstr::{ByteSlice as _, ByteVec as _};
 use perfetto_protos::{
-    debug_annotation::{DebugAnnotation, debug_annotation},
+    counter_descriptor::{CounterDescriptor, counter_descriptor},
+    debug_annotation::{DebugAnnotation, DebugAnnotationName, debug_annotation},
     interned_data::InternedData,
     log_message::{LogMessage, LogMessageBody},
     process_descriptor::ProcessDescriptor,
+    process_tree::{ProcessTree, process_tree},
     thread_descriptor::ThreadDescriptor,
     trace::Trace,
     trace_packet::{TracePacket, trace_packet},
     track_descriptor::{TrackDescriptor, track_descriptor},
-    track_event::{TrackEvent, track_event},
+    track_event::{EventName, TrackEvent, track_event},
 };
 use protobuf::{EnumOrUnknown, Message as _, MessageField};
 
 use crate::{Pid, event::Event};
 
-const TRACK_NAME: &str = "Processes";
+/// Write buffered packets once they add up to at least this many bytes.
+const MAX_BUFFERED_BYTES: u64 = 1024 * 1024;
 
 #[derive(Debug, Default)]
 pub struct PerfettoOutputOptions {
     pub logs: bool,
+
+    /// Write a slice for each syscall, nested in the slice of its process
+    /// (or on its own track for threads and processes that haven't exec'd).
+    pub syscalls: bool,
+
+    /// Only write syscall slices for these syscalls. Writes slices for all
+    /// syscalls if empty.
+    pub syscall_filter: Vec<String>,
+
+    /// Write counter tracks for the number of live processes, and for the
+    /// open fds, syscalls, and bytes read and written by each process.
+    pub counters: bool,
 }
 
 pub struct PerfettoOutput<W: std::io::Write> {
-    writer: W,
+    /// The writer to write packets to. Only taken by [`PerfettoOutput::finish`].
+    writer: Option<W>,
     options: PerfettoOutputOptions,
     trusted_packet_sequence_id: trace_packet::Optional_trusted_packet_sequence_id,
     track_uuids_by_pid: HashMap<Pid, u64>,
+    syscall_track_uuids_by_pid: HashMap<Pid, u64>,
+    fork_flow_ids_by_pid: HashMap<Pid, u64>,
+    thread_pids_by_tgid: HashMap<Pid, HashSet<Pid>>,
+    reap_flow_ids_by_pid: HashMap<Pid, u64>,
+    execed_pids: HashSet<Pid>,
+    suspended_pids: HashSet<Pid>,
+    live_pids: HashSet<Pid>,
+    stopped_pids: HashSet<Pid>,
+    live_processes_track_uuid: Option<u64>,
+    process_counters_by_pid: HashMap<Pid, ProcessCounters>,
     log_body_iid: u64,
+    interned_names: InternedNames,
     packets: Vec<TracePacket>,
+    buffered: Trace,
+    buffered_bytes: u64,
+    last_timestamp: Option<u64>,
     root_track_uuid: Option<u64>,
 }
 
@@ -39,13 +69,18 @@ impl<W: std::io::Write> PerfettoOutput<W> {
             trace_packet::Optional_trusted_packet_sequence_id::TrustedPacketSequenceId(
                 rand::random(),
             );
-        let mut packets = vec![];
+        // Start the sequence with a clean slate, so interned strings from
+        // the first packets onward are valid
+        let mut packets = vec![TracePacket {
+            optional_trusted_packet_sequence_id: Some(trusted_packet_sequence_id.clone()),
+            sequence_flags: Some(trace_packet::SequenceFlags::SEQ_INCREMENTAL_STATE_CLEARED as u32),
+            ..Default::default()
+        }];
 
         let root_track_uuid = if options.logs {
             let root_track_uuid = rand::random();
             packets.push(TracePacket {
                 optional_trusted_packet_sequence_id: Some(trusted_packet_sequence_id.clone()),
-                sequence_flags: Some(1),
                 data: Some(trace_packet::Data::TrackDescriptor(TrackDescriptor {
                     uuid: Some(root_track_uuid),
                     static_or_dynamic_name: Some(track_descriptor::Static_or_dynamic_name::Name(
@@ -69,20 +104,56 @@ impl<W: std::io::Write> PerfettoOutput<W> {
             None
         };
 
+        let live_processes_track_uuid = if options.counters {
+            let live_processes_track_uuid = rand::random();
+            packets.push(TracePacket {
+                optional_trusted_packet_sequence_id: Some(trusted_packet_sequence_id.clone()),
+                data: Some(trace_packet::Data::TrackDescriptor(TrackDescriptor {
+                    uuid: Some(live_processes_track_uuid),
+                    static_or_dynamic_name: Some(track_descriptor::Static_or_dynamic_name::Name(
+                        "Live processes".into(),
+                    )),
+                    counter: MessageField::some(CounterDescriptor {
+                        unit: Some(EnumOrUnknown::new(counter_descriptor::Unit::UNIT_COUNT)),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            });
+            Some(live_processes_track_uuid)
+        } else {
+            None
+        };
+
         Self {
-            writer,
+            writer: Some(writer),
             options,
             trusted_packet_sequence_id: trusted_packet_sequence_id,
             track_uuids_by_pid: HashMap::new(),
+            syscall_track_uuids_by_pid: HashMap::new(),
+            fork_flow_ids_by_pid: HashMap::new(),
+            thread_pids_by_tgid: HashMap::new(),
+            reap_flow_ids_by_pid: HashMap::new(),
+            execed_pids: HashSet::new(),
+            suspended_pids: HashSet::new(),
+            live_pids: HashSet::new(),
+            stopped_pids: HashSet::new(),
+            live_processes_track_uuid,
+            process_counters_by_pid: HashMap::new(),
             log_body_iid: 1,
+            interned_names: InternedNames::default(),
             packets,
+            buffered: Trace::default(),
+            buffered_bytes: 0,
+            last_timestamp: None,
             root_track_uuid,
         }
     }
 
     pub fn output_event(&mut self, event: Event) -> Result<(), Box<dyn std::error::Error>> {
         let pid = event.pid;
-        let mut track_uuid = *self
+        let track_uuid = *self
             .track_uuids_by_pid
             .entry(pid)
             .or_insert_with(|| rand::random());
@@ -96,6 +167,34 @@ impl<W: std::io::Write> PerfettoOutput<W> {
             let log_body_iid = self.log_body_iid;
             self.log_body_iid += 1;
 
+            let syscall_result = match &event.strace.event {
+                crate::strace::Event::Syscall(syscall) => syscall.result().ok(),
+                crate::strace::Event::Resumed(resumed) => resumed.result().ok(),
+                _ => None,
+            };
+            let syscall_error = syscall_result
+                .as_ref()
+                .filter(|result| result.is_failed())
+                .and_then(|result| result.error);
+            let debug_annotations = syscall_error
+                .into_iter()
+                .flat_map(|error| {
+                    let name = error.name.map(|name| DebugAnnotation {
+                        name_field: Some(debug_annotation::Name_field::Name("errno".to_string())),
+                        value: Some(debug_annotation::Value::StringValue(name.to_string())),
+                        ..Default::default()
+                    });
+                    let number = error.number.map(|number| DebugAnnotation {
+                        name_field: Some(debug_annotation::Name_field::Name(
+                            "errno_number".to_string(),
+                        )),
+                        value: Some(debug_annotation::Value::IntValue(number.into())),
+                        ..Default::default()
+                    });
+                    name.into_iter().chain(number)
+                })
+                .collect();
+
             Some(TracePacket {
                 timestamp: Some(timestamp),
                 optional_trusted_packet_sequence_id: Some(self.trusted_packet_sequence_id.clone()),
@@ -111,6 +210,7 @@ impl<W: std::io::Write> PerfettoOutput<W> {
                     track_uuid: self.root_track_uuid,
                     name_field: Some(track_event::Name_field::Name("Log".into())),
                     type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_INSTANT)),
+                    debug_annotations,
                     log_message: MessageField::some(LogMessage {
                         body_iid: Some(log_body_iid),
                         ..Default::default()
@@ -123,11 +223,62 @@ impl<W: std::io::Write> PerfettoOutput<W> {
             None
         };
 
+        // `exit` and `exit_group` never return, and the process's track
+        // gets ended when it stops
+        let is_stop = matches!(event.kind, crate::event::EventKind::StopProcess(_));
+        let syscall_slice = match &event.strace.event {
+            crate::strace::Event::Syscall(syscall) => {
+                self.options.syscalls
+                    && !is_stop
+                    && (self.options.syscall_filter.is_empty()
+                        || self
+                            .options
+                            .syscall_filter
+                            .iter()
+                            .any(|name| name == syscall.name))
+            }
+            _ => false,
+        };
+
+        // Arrows to draw from the syscall's slice
+        let mut syscall_flow_ids = vec![];
+        // Processes only become live when they fork or exec. Lines after a
+        // process stops (like the `+++ exited` line after `exit_group`)
+        // don't bring it back
+        let live_pids_before = self.live_pids.len();
+        match &event.kind {
+            crate::event::EventKind::StopProcess(stop_process_event) => {
+                if !stop_process_event.thread {
+                    self.live_pids.remove(&event.tgid);
+                    self.stopped_pids.insert(event.tgid);
+                }
+            }
+            crate::event::EventKind::ForkProcess(fork_process_event) => {
+                if !self.stopped_pids.contains(&event.tgid) {
+                    self.live_pids.insert(event.tgid);
+                }
+                if !fork_process_event.thread {
+                    // The child's pid may have been used by an earlier process
+                    self.stopped_pids.remove(&fork_process_event.child_pid);
+                    self.live_pids.insert(fork_process_event.child_pid);
+                }
+            }
+            crate::event::EventKind::ExecProcess(_) => {
+                if !self.stopped_pids.contains(&event.tgid) {
+                    self.live_pids.insert(event.tgid);
+                }
+            }
+            _ => {}
+        }
+
         match event.kind {
             crate::event::EventKind::ExecProcess(exec_process_event) => {
+                self.execed_pids.insert(pid);
+                self.syscall_track_uuids_by_pid.remove(&pid);
+
                 if exec_process_event.re_exec {
                     // If the `exec` happened on an existing track, end the
-                    // current track first
+                    // current slice first. The process keeps its track
 
                     self.packets.push(TracePacket {
                         timestamp: Some(timestamp),
@@ -141,16 +292,29 @@ impl<W: std::io::Write> PerfettoOutput<W> {
                         })),
                         ..Default::default()
                     });
-
-                    // Generate a new UUID for the new exec track
-                    track_uuid = rand::random();
-                    self.track_uuids_by_pid.insert(pid, track_uuid);
                 }
 
+                // Draw an arrow from the syscall that forked the process, and
+                // prepare an arrow from the end of the process's slice to
+                // where it gets reaped. The same reap arrow is used if the
+                // process execs again, so it goes through each exec's slice
+                let fork_flow_id = self.fork_flow_ids_by_pid.remove(&pid);
+                let reap_flow_id = *self
+                    .reap_flow_ids_by_pid
+                    .entry(pid)
+                    .or_insert_with(rand::random);
+
                 let command_name = exec_process_event
                     .exec
                     .command_name()
                     .map(|command_name| command_name.to_owned());
+                let cmdline: Vec<String> = exec_process_event
+                    .exec
+                    .args
+                    .iter()
+                    .flatten()
+                    .map(|arg| arg.to_str_lossy().into_owned())
+                    .collect();
                 let debug_annotations = exec_process_event
                     .exec
                     .command
@@ -205,13 +369,32 @@ impl<W: std::io::Write> PerfettoOutput<W> {
                         optional_trusted_packet_sequence_id: Some(
                             self.trusted_packet_sequence_id.clone(),
                         ),
-                        sequence_flags: Some(1),
+                        data: Some(trace_packet::Data::ProcessTree(ProcessTree {
+                            processes: vec![process_tree::Process {
+                                pid: Some(pid),
+                                ppid: event.parent_pid,
+                                cmdline: cmdline.clone(),
+                                ..Default::default()
+                            }],
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    },
+                    TracePacket {
+                        timestamp: Some(timestamp),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
                         data: Some(trace_packet::Data::TrackDescriptor(TrackDescriptor {
                             uuid: Some(track_uuid),
-                            parent_uuid: self.root_track_uuid,
-                            static_or_dynamic_name: Some(
-                                track_descriptor::Static_or_dynamic_name::Name(TRACK_NAME.into()),
-                            ),
+                            process: MessageField::some(ProcessDescriptor {
+                                pid: Some(pid),
+                                process_name: command_name
+                                    .as_ref()
+                                    .map(|name| name.to_str_lossy().into_owned()),
+                                cmdline,
+                                ..Default::default()
+                            }),
                             ..Default::default()
                         })),
                         ..Default::default()
@@ -227,16 +410,72 @@ impl<W: std::io::Write> PerfettoOutput<W> {
                             name_field: command_name
                                 .map(|name| track_event::Name_field::Name(name.to_string())),
                             debug_annotations,
+                            flow_ids: vec![reap_flow_id],
+                            terminating_flow_ids: fork_flow_id.into_iter().collect(),
                             ..Default::default()
                         })),
                         ..Default::default()
                     },
                 ]);
                 self.packets.extend(log_packet);
+
+                if self.options.counters && !self.process_counters_by_pid.contains_key(&pid) {
+                    let process_counters = self.process_counters(track_uuid);
+                    self.process_counters_by_pid.insert(pid, process_counters);
+                }
+            }
+            crate::event::EventKind::StopProcess(stop_process_event)
+                if stop_process_event.thread =>
+            {
+                // A thread exiting doesn't stop its process
+                self.track_uuids_by_pid.remove(&pid);
+                self.syscall_track_uuids_by_pid.remove(&pid);
+                self.fork_flow_ids_by_pid.remove(&pid);
+                if let Some(thread_pids) = self.thread_pids_by_tgid.get_mut(&event.tgid) {
+                    thread_pids.remove(&pid);
+                }
+                self.packets.extend(log_packet);
             }
             crate::event::EventKind::StopProcess(_) => {
+                // The process could be stopped from any of its threads
+                // (with `exit_group`)
+                let track_uuid = self
+                    .track_uuids_by_pid
+                    .remove(&event.tgid)
+                    .unwrap_or(track_uuid);
                 self.track_uuids_by_pid.remove(&pid);
+                self.syscall_track_uuids_by_pid.remove(&event.tgid);
+                self.syscall_track_uuids_by_pid.remove(&pid);
+                self.fork_flow_ids_by_pid.remove(&event.tgid);
+                self.fork_flow_ids_by_pid.remove(&pid);
+                self.execed_pids.remove(&event.tgid);
+                self.remove_threads(event.tgid);
+
+                // The process's fds all get closed when it stops
+                if let Some(process_counters) = self.process_counters_by_pid.remove(&event.tgid) {
+                    self.counter_value(timestamp, process_counters.open_fds_track_uuid, 0);
+                }
+
+                self.execed_pids.remove(&pid);
                 self.packets.extend(log_packet);
+
+                // End the suspended slice first, if the process was killed
+                // while it was suspended
+                if self.suspended_pids.remove(&event.tgid) {
+                    self.packets.push(TracePacket {
+                        timestamp: Some(timestamp),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
+                        data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                            track_uuid: Some(track_uuid),
+                            type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_SLICE_END)),
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    });
+                }
+
                 self.packets.push(TracePacket {
                     timestamp: Some(timestamp),
                     optional_trusted_packet_sequence_id: Some(
@@ -250,17 +489,918 @@ impl<W: std::io::Write> PerfettoOutput<W> {
                     ..Default::default()
                 });
             }
-            crate::event::EventKind::ForkProcess(_) | crate::event::EventKind::Log => {
+            crate::event::EventKind::FileAccess(file_access_event) => {
+                // Show the file access on the track of the process that owns it
+                if let Some(process_track_uuid) = self.owning_track_uuid(pid, event.owner_pid) {
+                    let name = match &event.strace.event {
+                        crate::strace::Event::Syscall(syscall) => syscall.name,
+                        _ => "file access",
+                    };
+                    let debug_annotations = [
+                        DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "paths".to_string(),
+                            )),
+                            array_values: file_access_event
+                                .accesses
+                                .iter()
+                                .map(|access| DebugAnnotation {
+                                    value: Some(debug_annotation::Value::StringValue(
+                                        access.path.to_str_lossy().into_owned(),
+                                    )),
+                                    ..Default::default()
+                                })
+                                .collect(),
+                            ..Default::default()
+                        },
+                        DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "modes".to_string(),
+                            )),
+                            array_values: file_access_event
+                                .accesses
+                                .iter()
+                                .map(|access| DebugAnnotation {
+                                    value: Some(debug_annotation::Value::StringValue(
+                                        access.mode.as_str().to_string(),
+                                    )),
+                                    ..Default::default()
+                                })
+                                .collect(),
+                            ..Default::default()
+                        },
+                        DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "success".to_string(),
+                            )),
+                            value: Some(debug_annotation::Value::BoolValue(
+                                file_access_event.success,
+                            )),
+                            ..Default::default()
+                        },
+                    ]
+                    .into_iter()
+                    .chain(file_access_event.flags.map(|flags| DebugAnnotation {
+                        name_field: Some(debug_annotation::Name_field::Name("flags".to_string())),
+                        value: Some(debug_annotation::Value::StringValue(flags)),
+                        ..Default::default()
+                    }))
+                    .chain(file_access_event.error.map(|error| DebugAnnotation {
+                        name_field: Some(debug_annotation::Name_field::Name("error".to_string())),
+                        value: Some(debug_annotation::Value::StringValue(error)),
+                        ..Default::default()
+                    }))
+                    .collect();
+
+                    self.packets.push(TracePacket {
+                        timestamp: Some(timestamp),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
+                        data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                            track_uuid: Some(process_track_uuid),
+                            type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_INSTANT)),
+                            name_field: Some(track_event::Name_field::Name(name.to_string())),
+                            debug_annotations,
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    });
+                }
+
                 self.packets.extend(log_packet);
             }
+            crate::event::EventKind::Signal(signal_event) => {
+                // Show the signal on the track of the process that owns it
+                if let Some(process_track_uuid) = self.owning_track_uuid(pid, event.owner_pid) {
+                    // Draw an arrow from the syscall that sent the signal,
+                    // starting at an instant on the track of the process that
+                    // owns the sender
+                    let sent_by_flow_id = signal_event.sent_by.as_ref().and_then(|sent_by| {
+                        let sender_track_uuid =
+                            self.owning_track_uuid(sent_by.pid, sent_by.owner_pid)?;
+                        let sent_at = sent_by
+                            .timestamp
+                            .as_nanosecond()
+                            .try_into()
+                            .expect("timestamp out of range");
+                        let flow_id = rand::random();
+
+                        self.packets.push(TracePacket {
+                            timestamp: Some(sent_at),
+                            optional_trusted_packet_sequence_id: Some(
+                                self.trusted_packet_sequence_id.clone(),
+                            ),
+                            data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                                track_uuid: Some(sender_track_uuid),
+                                type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_INSTANT)),
+                                name_field: Some(track_event::Name_field::Name(
+                                    sent_by.syscall.clone(),
+                                )),
+                                debug_annotations: vec![DebugAnnotation {
+                                    name_field: Some(debug_annotation::Name_field::Name(
+                                        "signal".to_string(),
+                                    )),
+                                    value: Some(debug_annotation::Value::StringValue(
+                                        signal_event.signal.clone(),
+                                    )),
+                                    ..Default::default()
+                                }],
+                                flow_ids: vec![flow_id],
+                                ..Default::default()
+                            })),
+                            ..Default::default()
+                        });
+
+                        Some(flow_id)
+                    });
+
+                    let debug_annotations = signal_event
+                        .code
+                        .map(|code| DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "code".to_string(),
+                            )),
+                            value: Some(debug_annotation::Value::StringValue(code)),
+                            ..Default::default()
+                        })
+                        .into_iter()
+                        .chain(signal_event.sender_pid.map(|sender_pid| DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "sender_pid".to_string(),
+                            )),
+                            value: Some(debug_annotation::Value::IntValue(sender_pid.into())),
+                            ..Default::default()
+                        }))
+                        .chain(signal_event.status.map(|status| DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "status".to_string(),
+                            )),
+                            value: Some(debug_annotation::Value::StringValue(status)),
+                            ..Default::default()
+                        }))
+                        .chain(signal_event.sent_by.into_iter().flat_map(|sent_by| {
+                            [
+                                DebugAnnotation {
+                                    name_field: Some(debug_annotation::Name_field::Name(
+                                        "sent_by_pid".to_string(),
+                                    )),
+                                    value: Some(debug_annotation::Value::IntValue(
+                                        sent_by.pid.into(),
+                                    )),
+                                    ..Default::default()
+                                },
+                                DebugAnnotation {
+                                    name_field: Some(debug_annotation::Name_field::Name(
+                                        "sent_by_syscall".to_string(),
+                                    )),
+                                    value: Some(debug_annotation::Value::StringValue(
+                                        sent_by.syscall,
+                                    )),
+                                    ..Default::default()
+                                },
+                            ]
+                        }))
+                        .collect();
+
+                    self.packets.push(TracePacket {
+                        timestamp: Some(timestamp),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
+                        data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                            track_uuid: Some(process_track_uuid),
+                            type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_INSTANT)),
+                            name_field: Some(track_event::Name_field::Name(signal_event.signal)),
+                            debug_annotations,
+                            terminating_flow_ids: sent_by_flow_id.into_iter().collect(),
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    });
+                }
+
+                self.packets.extend(log_packet);
+            }
+            crate::event::EventKind::SuspendProcess(suspend_process_event) => {
+                // Show the suspended time as a slice nested in the process's
+                // slice
+                if self.execed_pids.contains(&pid) && self.suspended_pids.insert(pid) {
+                    self.packets.push(TracePacket {
+                        timestamp: Some(timestamp),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
+                        data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                            track_uuid: Some(track_uuid),
+                            type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_SLICE_BEGIN)),
+                            name_field: Some(track_event::Name_field::Name("suspended".into())),
+                            debug_annotations: vec![DebugAnnotation {
+                                name_field: Some(debug_annotation::Name_field::Name(
+                                    "signal".to_string(),
+                                )),
+                                value: Some(debug_annotation::Value::StringValue(
+                                    suspend_process_event.signal,
+                                )),
+                                ..Default::default()
+                            }],
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    });
+                }
+
+                self.packets.extend(log_packet);
+            }
+            crate::event::EventKind::ContinueProcess(_) => {
+                self.packets.extend(log_packet);
+
+                if self.suspended_pids.remove(&pid) {
+                    self.packets.push(TracePacket {
+                        timestamp: Some(timestamp),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
+                        data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                            track_uuid: Some(track_uuid),
+                            type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_SLICE_END)),
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    });
+                }
+            }
+            crate::event::EventKind::ReapProcess(reap_process_event) => {
+                // Show the reaped child on the track of the process that
+                // owns it
+                let process_track_uuid = self.owning_track_uuid(pid, event.owner_pid);
+
+                // Draw an arrow from the end of the child's slice, if it
+                // exec'd
+                let reap_flow_id = self
+                    .reap_flow_ids_by_pid
+                    .remove(&reap_process_event.child_pid);
+
+                if let Some(process_track_uuid) = process_track_uuid {
+                    let reaped_at = reap_process_event
+                        .reaped_at
+                        .as_nanosecond()
+                        .try_into()
+                        .expect("timestamp out of range");
+                    let debug_annotations = [
+                        DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "child_pid".to_string(),
+                            )),
+                            value: Some(debug_annotation::Value::IntValue(
+                                reap_process_event.child_pid.into(),
+                            )),
+                            ..Default::default()
+                        },
+                        DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "wait_ns".to_string(),
+                            )),
+                            value: Some(debug_annotation::Value::UintValue(
+                                reap_process_event
+                                    .wait_duration
+                                    .as_nanos()
+                                    .try_into()
+                                    .unwrap_or(u64::MAX),
+                            )),
+                            ..Default::default()
+                        },
+                        DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "orphan".to_string(),
+                            )),
+                            value: Some(debug_annotation::Value::BoolValue(
+                                reap_process_event.orphan,
+                            )),
+                            ..Default::default()
+                        },
+                    ]
+                    .into_iter()
+                    .chain(reap_process_event.exited_at.map(|exited_at| {
+                        let zombie_duration =
+                            reap_process_event.reaped_at.duration_since(exited_at);
+                        DebugAnnotation {
+                            name_field: Some(debug_annotation::Name_field::Name(
+                                "zombie_ns".to_string(),
+                            )),
+                            value: Some(debug_annotation::Value::IntValue(
+                                zombie_duration.as_nanos().try_into().unwrap_or(i64::MAX),
+                            )),
+                            ..Default::default()
+                        }
+                    }))
+                    .collect();
+
+                    self.packets.push(TracePacket {
+                        timestamp: Some(reaped_at),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
+                        data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                            track_uuid: Some(process_track_uuid),
+                            type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_INSTANT)),
+                            name_field: Some(track_event::Name_field::Name("reaped".into())),
+                            debug_annotations,
+                            terminating_flow_ids: reap_flow_id.into_iter().collect(),
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    });
+                }
+
+                self.packets.extend(log_packet);
+            }
+            crate::event::EventKind::ForkProcess(fork_process_event) => {
+                // The child's pid may have been used by an earlier process
+                let child_pid = fork_process_event.child_pid;
+                self.syscall_track_uuids_by_pid.remove(&child_pid);
+                self.fork_flow_ids_by_pid.remove(&child_pid);
+                self.reap_flow_ids_by_pid.remove(&child_pid);
+
+                // Record the new process or thread, so the parent pid and
+                // thread group show up in Perfetto's `process` and `thread`
+                // tables
+                let process_tree = if fork_process_event.thread {
+                    ProcessTree {
+                        threads: vec![process_tree::Thread {
+                            tid: Some(child_pid),
+                            tgid: Some(fork_process_event.child_tgid),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }
+                } else {
+                    ProcessTree {
+                        processes: vec![process_tree::Process {
+                            pid: Some(child_pid),
+                            ppid: Some(event.tgid),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }
+                };
+                self.packets.push(TracePacket {
+                    timestamp: Some(timestamp),
+                    optional_trusted_packet_sequence_id: Some(
+                        self.trusted_packet_sequence_id.clone(),
+                    ),
+                    data: Some(trace_packet::Data::ProcessTree(process_tree)),
+                    ..Default::default()
+                });
+
+                // Give new threads their thread track up front, so they show
+                // up in their process even without syscall slices
+                if fork_process_event.thread {
+                    self.thread_pids_by_tgid
+                        .entry(fork_process_event.child_tgid)
+                        .or_default()
+                        .insert(child_pid);
+                    self.syscall_track_uuid(child_pid, fork_process_event.child_tgid, timestamp);
+                }
+
+                // Draw an arrow from the fork to the child's first exec. The
+                // arrow starts from the syscall's slice if there is one, or
+                // else from an instant on the track of the process that owns
+                // the parent
+                if !fork_process_event.thread && syscall_slice {
+                    let fork_flow_id = rand::random();
+                    self.fork_flow_ids_by_pid.insert(child_pid, fork_flow_id);
+                    syscall_flow_ids.push(fork_flow_id);
+                } else if !fork_process_event.thread
+                    && let Some(process_track_uuid) = self.owning_track_uuid(pid, event.owner_pid)
+                {
+                    let name = match &event.strace.event {
+                        crate::strace::Event::Syscall(syscall) => syscall.name,
+                        _ => "fork",
+                    };
+                    let fork_flow_id = rand::random();
+                    self.fork_flow_ids_by_pid.insert(child_pid, fork_flow_id);
+
+                    self.packets.push(TracePacket {
+                        timestamp: Some(timestamp),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
+                        data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                            track_uuid: Some(process_track_uuid),
+                            type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_INSTANT)),
+                            name_field: Some(track_event::Name_field::Name(name.to_string())),
+                            debug_annotations: vec![DebugAnnotation {
+                                name_field: Some(debug_annotation::Name_field::Name(
+                                    "child_pid".to_string(),
+                                )),
+                                value: Some(debug_annotation::Value::IntValue(child_pid.into())),
+                                ..Default::default()
+                            }],
+                            flow_ids: vec![fork_flow_id],
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    });
+                }
+
+                self.packets.extend(log_packet);
+            }
+            crate::event::EventKind::Log => {
+                // A thread calling `execve` kills every other thread in the
+                // group, and takes over the leader's pid
+                if matches!(event.strace.event, crate::strace::Event::Superseded { .. }) {
+                    self.remove_threads(event.tgid);
+                }
+
+                self.packets.extend(log_packet);
+            }
+        };
+
+        if let Some(live_processes_track_uuid) = self.live_processes_track_uuid
+            && self.live_pids.len() != live_pids_before
+        {
+            let live_processes = self.live_pids.len().try_into().unwrap_or(i64::MAX);
+            self.counter_value(timestamp, live_processes_track_uuid, live_processes);
+        }
+
+        // Count syscalls from threads and processes that haven't exec'd
+        // towards the process that owns them
+        let counters_pid = self
+            .owning_pid(event.tgid, event.owner_pid)
+            .filter(|pid| self.process_counters_by_pid.contains_key(pid));
+        if !is_stop
+            && let Some(counters_pid) = counters_pid
+            && let crate::strace::Event::Syscall(syscall) = &event.strace.event
+        {
+            let process_counters = self
+                .process_counters_by_pid
+                .get_mut(&counters_pid)
+                .expect("process counters not found");
+            let mut values = vec![];
+
+            process_counters.syscalls += 1;
+            values.push((
+                process_counters.syscalls_track_uuid,
+                process_counters.syscalls,
+            ));
+
+            let bytes = syscall
+                .result()
+                .ok()
+                .and_then(|result| result.success_i64());
+            match (syscall.name, bytes) {
+                (
+                    "read" | "pread64" | "readv" | "preadv" | "preadv2" | "recvfrom" | "recvmsg",
+                    Some(bytes),
+                ) => {
+                    process_counters.bytes_read += bytes;
+                    values.push((
+                        process_counters.bytes_read_track_uuid,
+                        process_counters.bytes_read,
+                    ));
+                }
+                (
+                    "write" | "pwrite64" | "writev" | "pwritev" | "pwritev2" | "sendto" | "sendmsg",
+                    Some(bytes),
+                ) => {
+                    process_counters.bytes_written += bytes;
+                    values.push((
+                        process_counters.bytes_written_track_uuid,
+                        process_counters.bytes_written,
+                    ));
+                }
+                _ => {}
+            }
+
+            // Only the process's own fd table is tracked
+            if counters_pid == event.tgid
+                && let Some(open_fds) = event.open_fds
+                && process_counters.open_fds != Some(open_fds)
+            {
+                process_counters.open_fds = Some(open_fds);
+                values.push((
+                    process_counters.open_fds_track_uuid,
+                    open_fds.try_into().unwrap_or(i64::MAX),
+                ));
+            }
+
+            for (counter_track_uuid, value) in values {
+                self.counter_value(timestamp, counter_track_uuid, value);
+            }
+        }
+
+        if syscall_slice && let crate::strace::Event::Syscall(syscall) = &event.strace.event {
+            let syscall_track_uuid = self.syscall_track_uuid(pid, event.tgid, timestamp);
+            let duration: u64 = syscall.duration.as_nanos().try_into().unwrap_or(u64::MAX);
+
+            self.packets.extend([
+                TracePacket {
+                    timestamp: Some(timestamp),
+                    optional_trusted_packet_sequence_id: Some(
+                        self.trusted_packet_sequence_id.clone(),
+                    ),
+                    data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                        track_uuid: Some(syscall_track_uuid),
+                        type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_SLICE_BEGIN)),
+                        name_field: Some(track_event::Name_field::Name(syscall.name.to_string())),
+                        debug_annotations: vec![
+                            DebugAnnotation {
+                                name_field: Some(debug_annotation::Name_field::Name(
+                                    "args".to_string(),
+                                )),
+                                value: Some(debug_annotation::Value::StringValue(
+                                    syscall.args_string.value.to_string(),
+                                )),
+                                ..Default::default()
+                            },
+                            DebugAnnotation {
+                                name_field: Some(debug_annotation::Name_field::Name(
+                                    "result".to_string(),
+                                )),
+                                value: Some(debug_annotation::Value::StringValue(
+                                    syscall.result_string.value.to_string(),
+                                )),
+                                ..Default::default()
+                            },
+                        ],
+                        flow_ids: syscall_flow_ids,
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                },
+                TracePacket {
+                    timestamp: Some(timestamp.saturating_add(duration)),
+                    optional_trusted_packet_sequence_id: Some(
+                        self.trusted_packet_sequence_id.clone(),
+                    ),
+                    data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                        track_uuid: Some(syscall_track_uuid),
+                        type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_SLICE_END)),
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                },
+            ]);
+        }
+
+        self.buffer_packets()?;
+
+        Ok(())
+    }
+
+    /// End the slices of any processes that were still running, then write
+    /// all buffered packets and flush the writer. Slices are ended at the
+    /// last timestamp seen in the trace, and marked with `truncated`.
+    ///
+    /// If the output is dropped without calling `finish`, the same is done
+    /// on a best-effort basis (ignoring any errors).
+    pub fn finish(mut self) -> Result<W, Box<dyn std::error::Error>> {
+        let result = self.write_remaining();
+        let writer = self.writer.take().expect("writer already taken");
+        result?;
+
+        Ok(writer)
+    }
+
+    fn write_remaining(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(timestamp) = self.last_timestamp {
+            let mut running_pids: Vec<_> = self.execed_pids.iter().copied().collect();
+            running_pids.sort();
+
+            for pid in running_pids {
+                let Some(&track_uuid) = self.track_uuids_by_pid.get(&pid) else {
+                    continue;
+                };
+
+                // End the suspended slice first, since it's nested in the
+                // process's slice
+                let open_slices = if self.suspended_pids.remove(&pid) {
+                    2
+                } else {
+                    1
+                };
+                for _ in 0..open_slices {
+                    self.packets.push(TracePacket {
+                        timestamp: Some(timestamp),
+                        optional_trusted_packet_sequence_id: Some(
+                            self.trusted_packet_sequence_id.clone(),
+                        ),
+                        data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                            track_uuid: Some(track_uuid),
+                            type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_SLICE_END)),
+                            debug_annotations: vec![DebugAnnotation {
+                                name_field: Some(debug_annotation::Name_field::Name(
+                                    "truncated".to_string(),
+                                )),
+                                value: Some(debug_annotation::Value::BoolValue(true)),
+                                ..Default::default()
+                            }],
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    });
+                }
+            }
+
+            self.execed_pids.clear();
+        }
+
+        self.buffer_packets()?;
+        self.write_buffered()?;
+        if let Some(writer) = &mut self.writer {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the packets for the current event into the buffer, then write
+    /// the buffer if it's gotten big enough.
+    fn buffer_packets(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        for mut packet in std::mem::take(&mut self.packets) {
+            self.interned_names.intern_packet(&mut packet);
+            self.last_timestamp = self.last_timestamp.max(packet.timestamp);
+            self.buffered_bytes += packet.compute_size();
+            self.buffered.packet.push(packet);
+        }
+
+        if self.buffered_bytes >= MAX_BUFFERED_BYTES {
+            self.write_buffered()?;
+        }
+
+        Ok(())
+    }
+
+    fn write_buffered(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let buffered = std::mem::take(&mut self.buffered);
+        self.buffered_bytes = 0;
+
+        if let Some(writer) = &mut self.writer
+            && !buffered.packet.is_empty()
+        {
+            buffered.write_to_writer(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Drop the state of each thread in a thread group. Used when the whole
+    /// group stops at once (with `exit_group`, or when a thread calls
+    /// `execve`), since the threads don't get stop events of their own.
+    fn remove_threads(&mut self, tgid: Pid) {
+        let thread_pids = self.thread_pids_by_tgid.remove(&tgid);
+        for thread_pid in thread_pids.into_iter().flatten() {
+            self.track_uuids_by_pid.remove(&thread_pid);
+            self.syscall_track_uuids_by_pid.remove(&thread_pid);
+            self.fork_flow_ids_by_pid.remove(&thread_pid);
+        }
+    }
+
+    /// Get the pid of the exec'd process that owns a process: the process
+    /// itself if it's exec'd, otherwise its owner (if it has one).
+    fn owning_pid(&self, pid: Pid, owner_pid: Option<Pid>) -> Option<Pid> {
+        [Some(pid), owner_pid]
+            .into_iter()
+            .flatten()
+            .find(|pid| self.execed_pids.contains(pid))
+    }
+
+    /// Get the track of the exec'd process that owns a process (see
+    /// [`Self::owning_pid`]).
+    fn owning_track_uuid(&self, pid: Pid, owner_pid: Option<Pid>) -> Option<u64> {
+        let owning_pid = self.owning_pid(pid, owner_pid)?;
+        self.track_uuids_by_pid.get(&owning_pid).copied()
+    }
+
+    /// Get the track to write syscall slices for a pid to. Exec'd processes
+    /// use their process track, so syscalls are nested in the process's
+    /// slice. Threads and processes that haven't exec'd get their own track
+    /// (since their syscalls can overlap with the process's syscalls).
+    /// Threads get a thread track in their process, and processes that
+    /// haven't exec'd get a process track of their own, so they can still
+    /// be found by pid.
+    fn syscall_track_uuid(&mut self, pid: Pid, tgid: Pid, timestamp: u64) -> u64 {
+        if let Some(track_uuid) = self.owning_track_uuid(pid, None) {
+            return track_uuid;
+        }
+
+        if let Some(&track_uuid) = self.syscall_track_uuids_by_pid.get(&pid) {
+            return track_uuid;
+        }
+
+        let track_descriptor = if pid == tgid {
+            TrackDescriptor {
+                static_or_dynamic_name: Some(track_descriptor::Static_or_dynamic_name::Name(
+                    format!("Process {pid}"),
+                )),
+                process: MessageField::some(ProcessDescriptor {
+                    pid: Some(pid),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        } else {
+            TrackDescriptor {
+                thread: MessageField::some(ThreadDescriptor {
+                    pid: Some(tgid),
+                    tid: Some(pid),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
         };
 
-        let perfetto_message = Trace {
-            packet: std::mem::take(&mut self.packets),
+        let track_uuid = rand::random();
+        self.syscall_track_uuids_by_pid.insert(pid, track_uuid);
+        self.packets.push(TracePacket {
+            timestamp: Some(timestamp),
+            optional_trusted_packet_sequence_id: Some(self.trusted_packet_sequence_id.clone()),
+            data: Some(trace_packet::Data::TrackDescriptor(TrackDescriptor {
+                uuid: Some(track_uuid),
+                ..track_descriptor
+            })),
             ..Default::default()
+        });
+
+        track_uuid
+    }
+
+    /// Create the counter tracks for a process, nested under the process's
+    /// track.
+    fn process_counters(&mut self, process_track_uuid: u64) -> ProcessCounters {
+        let process_counters = ProcessCounters {
+            open_fds_track_uuid: rand::random(),
+            syscalls_track_uuid: rand::random(),
+            bytes_read_track_uuid: rand::random(),
+            bytes_written_track_uuid: rand::random(),
+            open_fds: None,
+            syscalls: 0,
+            bytes_read: 0,
+            bytes_written: 0,
         };
-        perfetto_message.write_to_writer(&mut self.writer)?;
 
-        Ok(())
+        let counter_tracks = [
+            (
+                process_counters.open_fds_track_uuid,
+                "Open fds",
+                counter_descriptor::Unit::UNIT_COUNT,
+            ),
+            (
+                process_counters.syscalls_track_uuid,
+                "Syscalls",
+                counter_descriptor::Unit::UNIT_COUNT,
+            ),
+            (
+                process_counters.bytes_read_track_uuid,
+                "Bytes read",
+                counter_descriptor::Unit::UNIT_SIZE_BYTES,
+            ),
+            (
+                process_counters.bytes_written_track_uuid,
+                "Bytes written",
+                counter_descriptor::Unit::UNIT_SIZE_BYTES,
+            ),
+        ];
+        for (track_uuid, name, unit) in counter_tracks {
+            self.packets.push(TracePacket {
+                optional_trusted_packet_sequence_id: Some(self.trusted_packet_sequence_id.clone()),
+                data: Some(trace_packet::Data::TrackDescriptor(TrackDescriptor {
+                    uuid: Some(track_uuid),
+                    parent_uuid: Some(process_track_uuid),
+                    static_or_dynamic_name: Some(track_descriptor::Static_or_dynamic_name::Name(
+                        name.into(),
+                    )),
+                    counter: MessageField::some(CounterDescriptor {
+                        unit: Some(EnumOrUnknown::new(unit)),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            });
+        }
+
+        process_counters
+    }
+
+    fn counter_value(&mut self, timestamp: u64, track_uuid: u64, value: i64) {
+        self.packets.push(TracePacket {
+            timestamp: Some(timestamp),
+            optional_trusted_packet_sequence_id: Some(self.trusted_packet_sequence_id.clone()),
+            data: Some(trace_packet::Data::TrackEvent(TrackEvent {
+                track_uuid: Some(track_uuid),
+                type_: Some(EnumOrUnknown::new(track_event::Type::TYPE_COUNTER)),
+                counter_value_field: Some(track_event::Counter_value_field::CounterValue(value)),
+                ..Default::default()
+            })),
+            ..Default::default()
+        });
+    }
+}
+
+impl<W: std::io::Write> Drop for PerfettoOutput<W> {
+    fn drop(&mut self) {
+        // Write anything left over if `finish` wasn't called (e.g. when
+        // returning early on an error), so the output isn't cut off
+        if self.writer.is_some() {
+            let _ = self.write_remaining();
+        }
+    }
+}
+
+/// Counter tracks for an exec'd process. Syscalls and bytes are counted
+/// cumulatively, so the Perfetto UI can show them as a rate.
+struct ProcessCounters {
+    open_fds_track_uuid: u64,
+    syscalls_track_uuid: u64,
+    bytes_read_track_uuid: u64,
+    bytes_written_track_uuid: u64,
+    open_fds: Option<usize>,
+    syscalls: i64,
+    bytes_read: i64,
+    bytes_written: i64,
+}
+
+/// Event names and debug annotation names that have been interned on the
+/// packet sequence. Names are repeated across lots of events (command names,
+/// syscall names, and annotations like `args` and `env`), so each name is
+/// only written once, then referenced by its interned id.
+#[derive(Default)]
+struct InternedNames {
+    event_names: HashMap<String, u64>,
+    debug_annotation_names: HashMap<String, u64>,
+}
+
+impl InternedNames {
+    /// Replace the inline event name and debug annotation names of a track
+    /// event with interned ids. Names that haven't been interned yet are
+    /// added to the packet's interned data.
+    fn intern_packet(&mut self, packet: &mut TracePacket) {
+        let Some(trace_packet::Data::TrackEvent(track_event)) = &mut packet.data else {
+            return;
+        };
+
+        let mut new_event_names = vec![];
+        let mut new_debug_annotation_names = vec![];
+
+        if let Some(track_event::Name_field::Name(name)) = &mut track_event.name_field {
+            let iid = intern(&mut self.event_names, std::mem::take(name), |iid, name| {
+                new_event_names.push(EventName {
+                    iid: Some(iid),
+                    name: Some(name),
+                    ..Default::default()
+                });
+            });
+            track_event.name_field = Some(track_event::Name_field::NameIid(iid));
+        }
+
+        let mut debug_annotations: Vec<_> = track_event.debug_annotations.iter_mut().collect();
+        while let Some(debug_annotation) = debug_annotations.pop() {
+            if let Some(debug_annotation::Name_field::Name(name)) = &mut debug_annotation.name_field
+            {
+                let name = std::mem::take(name);
+                let iid = intern(&mut self.debug_annotation_names, name, |iid, name| {
+                    new_debug_annotation_names.push(DebugAnnotationName {
+                        iid: Some(iid),
+                        name: Some(name),
+                        ..Default::default()
+                    });
+                });
+                debug_annotation.name_field = Some(debug_annotation::Name_field::NameIid(iid));
+            }
+
+            // Nested annotations are named too (e.g. each env var)
+            debug_annotations.extend(&mut debug_annotation.dict_entries);
+        }
+
+        if !new_event_names.is_empty() || !new_debug_annotation_names.is_empty() {
+            let interned_data = packet.interned_data.mut_or_insert_default();
+            interned_data.event_names.extend(new_event_names);
+            interned_data
+                .debug_annotation_names
+                .extend(new_debug_annotation_names);
+        }
+
+        let sequence_flags = packet.sequence_flags.unwrap_or_default();
+        packet.sequence_flags =
+            Some(sequence_flags | trace_packet::SequenceFlags::SEQ_NEEDS_INCREMENTAL_STATE as u32);
+    }
+}
+
+/// Get the interned id for a string, calling `on_new` with a newly-assigned
+/// id if it wasn't interned yet. Ids start at 1, since 0 is reserved.
+fn intern(
+    interned: &mut HashMap<String, u64>,
+    name: String,
+    on_new: impl FnOnce(u64, String),
+) -> u64 {
+    if let Some(&iid) = interned.get(&name) {
+        return iid;
     }
+
+    let iid = u64::try_from(interned.len()).unwrap() + 1;
+    interned.insert(name.clone(), iid);
+    on_new(iid, name);
+    iid
 }
@@ -5,7 +5,9 @@ use blame_on::Blame;
 use crate::Pid;
 
 pub mod analyzer;
+pub mod errno;
 pub mod parser;
+pub mod stitcher;
 
 #[derive(Debug, Clone)]
 pub struct Line<'a> {
@@ -15,12 +17,50 @@ pub struct Line<'a> {
     pub event: Event<'a>,
 }
 
+impl Line<'_> {
+    /// When the line should be analyzed, relative to other lines. This is
+    /// the line's timestamp, except for `wait` syscalls, which are analyzed
+    /// when they return. A blocking wait starts before the child it reaps
+    /// exits, so it would otherwise be analyzed before the child's exit.
+    pub fn analyze_at(&self) -> jiff::Timestamp {
+        match &self.event {
+            Event::Syscall(syscall) if matches!(syscall.name, "wait4" | "waitpid" | "waitid") => {
+                jiff::SignedDuration::try_from(syscall.duration)
+                    .ok()
+                    .and_then(|duration| self.timestamp.checked_add(duration).ok())
+                    .unwrap_or(self.timestamp)
+            }
+            _ => self.timestamp,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Event<'a> {
     Syscall(SyscallEvent<'a>),
-    Signal { signal: &'a str },
+    Unfinished(UnfinishedSyscallEvent<'a>),
+    Resumed(ResumedSyscallEvent<'a>),
+    Signal {
+        signal: &'a str,
+    },
+
+    /// The process was stopped by a job-control signal (group-stop), printed
+    /// as `--- stopped by SIGSTOP ---`.
+    GroupStop {
+        signal: &'a str,
+    },
     Exited(ExitedEvent<'a>),
-    KilledBy { signal_string: Blame<&'a str> },
+    KilledBy {
+        signal_string: Blame<&'a str>,
+    },
+
+    /// The thread group leader was replaced by one of its threads calling
+    /// `execve`, which takes over the leader's pid. Printed as
+    /// `+++ superseded by execve +++`, or as
+    /// `+++ superseded by execve in pid <pid> +++` with the thread's old pid.
+    Superseded {
+        thread_pid: Option<Pid>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -37,18 +77,100 @@ impl<'a> SyscallEvent<'a> {
         Ok(args)
     }
 
-    fn result(&'a self) -> Result<SyscallResult<'a>, parser::StraceParseError> {
+    pub fn result(&'a self) -> Result<SyscallResult<'a>, parser::StraceParseError> {
         let result = parser::parse_syscall_result(self.result_string)?;
         Ok(result)
     }
 }
 
-pub(crate) struct SyscallResult<'a> {
+/// The first half of a syscall that was interrupted by another traced
+/// process, printed as `name(args <unfinished ...>`.
+#[derive(Debug, Clone)]
+pub struct UnfinishedSyscallEvent<'a> {
+    pub name: &'a str,
+    pub args_string: Blame<&'a str>,
+}
+
+/// The second half of an unfinished syscall, printed as
+/// `<... name resumed>args) = result <duration>`. `args_string` only contains
+/// the args that weren't printed in the unfinished half.
+#[derive(Debug, Clone)]
+pub struct ResumedSyscallEvent<'a> {
+    pub name: &'a str,
+    pub args_string: Blame<&'a str>,
+    pub result_string: Blame<&'a str>,
+    pub duration: std::time::Duration,
+}
+
+impl<'a> ResumedSyscallEvent<'a> {
+    pub fn result(&'a self) -> Result<SyscallResult<'a>, parser::StraceParseError> {
+        let result = parser::parse_syscall_result(self.result_string)?;
+        Ok(result)
+    }
+}
+
+/// The result of a syscall, such as `3`, `-1 ENOENT (No such file or
+/// directory)`, `0 (Timeout)`, or `? ERESTARTSYS (To be restarted if
+/// SA_RESTART is set)`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SyscallResult<'a> {
+    /// The returned value, or `None` if it's unknown (`?`).
     pub returned: Option<Value<'a>>,
-    #[expect(dead_code)]
+
+    /// The error, if the syscall failed.
+    pub error: Option<SyscallError<'a>>,
+
+    /// The explanation strace printed after the result, such as
+    /// `No such file or directory` or `Timeout`.
     pub message: Option<&'a str>,
 }
 
+impl SyscallResult<'_> {
+    /// Returns true if the syscall returned an error, not counting syscalls
+    /// that were interrupted and will be restarted.
+    pub fn is_failed(&self) -> bool {
+        self.error.as_ref().is_some_and(|error| !error.is_restart())
+    }
+
+    /// Returns the returned value as an integer, but only if the syscall
+    /// succeeded.
+    pub fn success_i32(&self) -> Option<i32> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        self.returned.as_ref()?.as_i32()
+    }
+
+    /// Returns the returned value as a 64-bit integer, but only if the
+    /// syscall succeeded. Used for byte counts, which can exceed `i32`.
+    pub fn success_i64(&self) -> Option<i64> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        self.returned.as_ref()?.as_i64()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallError<'a> {
+    /// The errno name, such as `ENOENT`. Can be `None` if strace printed
+    /// an unknown errno number (`-1 (errno 1234)`).
+    pub name: Option<&'a str>,
+
+    /// The Linux errno number, if known.
+    pub number: Option<i32>,
+}
+
+impl SyscallError<'_> {
+    /// Returns true for errors that mean the syscall was interrupted and
+    /// will be restarted (`ERESTARTSYS`, etc.).
+    pub fn is_restart(&self) -> bool {
+        self.number.is_some_and(errno::is_restart_errno)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExitedEvent<'a> {
     pub code_string: Blame<&'a str>,
@@ -139,12 +261,54 @@ impl Value<'_> {
     }
 
     pub(crate) fn as_i32(&self) -> Option<i32> {
-        if let Self::Expression(expr) = self {
-            expr.parse().ok()
-        } else {
-            None
+        match self {
+            Self::Expression(expr) => expr.parse().ok(),
+            // Decoded file descriptors are annotated with their path
+            // (e.g. `3</dev/null>`)
+            Self::Annotated { value, .. } => value.as_i32(),
+            _ => None,
         }
     }
+
+    pub(crate) fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Expression(expr) => expr.parse().ok(),
+            Self::Annotated { value, .. } => value.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Get the annotation of a value, such as the path of a decoded file
+    /// descriptor (`3</dev/null>`).
+    fn annotation(&self) -> Option<&bstr::BStr> {
+        match self {
+            Self::Annotated { annotation, .. } => Some(annotation),
+            Self::Commented { value, .. } => value.annotation(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the value is a set of flags including `flag`
+    /// (e.g. `O_RDONLY|O_CLOEXEC` includes `O_CLOEXEC`).
+    fn has_flag(&self, flag: &str) -> bool {
+        match self {
+            Self::Expression(expr) => expr.split('|').any(|expr_flag| expr_flag == flag),
+            Self::Commented { value, .. } => value.has_flag(flag),
+            _ => false,
+        }
+    }
+
+    /// Get the value of a named struct field.
+    fn field(&self, name: &str) -> Option<&Value<'_>> {
+        let Self::Struct(fields) = self else {
+            return None;
+        };
+
+        fields
+            .iter()
+            .find(|field| field.name == Some(name))
+            .map(|field| &field.value)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -164,6 +328,13 @@ impl Fields<'_> {
     fn value_at_index(&self, index: usize) -> Option<&Value<'_>> {
         self.values.get(index).map(|field| &field.value)
     }
+
+    fn value_named(&self, name: &str) -> Option<&Value<'_>> {
+        self.values
+            .iter()
+            .find(|field| field.name == Some(name))
+            .map(|field| &field.value)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1,12 +1,16 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+};
 
 use bstr::ByteSlice;
 
 use crate::{
     Pid,
     event::{
-        Event, EventKind, ExecProcessEvent, ForkProcessEvent, ProcessExec, ProcessStoppedReason,
-        StopProcessEvent,
+        ContinueProcessEvent, Event, EventKind, ExecProcessEvent, FileAccess, FileAccessEvent,
+        FileAccessMode, ForkProcessEvent, ProcessExec, ProcessStoppedReason, ReapProcessEvent,
+        SignalEvent, SignalSyscall, StopProcessEvent, SuspendProcessEvent,
     },
     strace::parser::StraceParseError,
 };
@@ -14,27 +18,72 @@ use crate::{
 #[derive(Default)]
 pub struct Analyzer {
     processes: HashMap<Pid, ProcessState>,
+    fd_tables: HashMap<SharedStateId, FdTable>,
+    fs_states: HashMap<SharedStateId, FsState>,
+    next_shared_state_id: u64,
+
+    /// The number of running processes using each fd table and filesystem
+    /// state.
+    shared_state_refs: HashMap<SharedStateId, usize>,
+
+    /// The pids of the running threads in each thread group (including the
+    /// leader), by tgid.
+    running_threads: HashMap<Pid, BTreeSet<Pid>>,
+
+    /// Signals sent with `kill` and similar syscalls that haven't been
+    /// delivered yet, by target pid and signal name. Dropped if the target
+    /// stops first.
+    sent_signals: HashMap<(Pid, String), SignalSyscall>,
 }
 
 impl Analyzer {
     pub fn analyze<'a>(&mut self, line: super::Line<'a>) -> Result<Event<'a>, StraceParseError> {
+        // Resolve paths before handling the syscall, since it could change
+        // the cwd (e.g. `chdir("..")`)
+        let paths = match &line.event {
+            super::Event::Syscall(event) => self.resolve_path_args(line.pid, event)?,
+            _ => vec![],
+        };
+
+        // Get the path of the fd for reads and writes
+        let fd_path = match &line.event {
+            super::Event::Syscall(event) => self.resolve_fd_path(line.pid, event)?,
+            _ => None,
+        };
+
         let kind = match &line.event {
             super::Event::Syscall(event) => match event.name {
                 "fork" | "vfork" | "clone" | "clone3" => {
                     let result = event.result()?;
+                    let args = event.args()?;
 
-                    let child_pid = result.returned.and_then(|value| value.as_i32());
+                    let clone_flags = match event.name {
+                        "clone" => args.value_named("flags"),
+                        "clone3" => args
+                            .value_at_index(0)
+                            .and_then(|clone_args| clone_args.field("flags")),
+                        _ => None,
+                    };
+                    let has_clone_flag =
+                        |flag| clone_flags.is_some_and(|flags| flags.has_flag(flag));
+                    let clone_flags = CloneFlags {
+                        thread: has_clone_flag("CLONE_THREAD"),
+                        // vfork implies `CLONE_VM|CLONE_VFORK`
+                        vm: event.name == "vfork" || has_clone_flag("CLONE_VM"),
+                        files: has_clone_flag("CLONE_FILES"),
+                        fs: has_clone_flag("CLONE_FS"),
+                    };
+
+                    let child_pid = result.success_i32();
                     child_pid.map_or(EventKind::Log, |child_pid| {
-                        self.handle_fork(&line, child_pid)
+                        self.handle_fork(&line, child_pid, clone_flags)
                     })
                 }
+                "execve" if event.result()?.is_failed() => file_access_event(event, &paths)?,
                 "execve" => {
                     let args = event.args()?;
 
-                    let command = args
-                        .value_at_index(0)
-                        .and_then(super::Value::to_bstring)
-                        .map(Cow::into_owned);
+                    let command = paths.first().cloned().flatten();
                     let exec_args =
                         args.value_at_index(1)
                             .and_then(super::Value::as_array)
@@ -71,28 +120,12 @@ impl Analyzer {
                         },
                     )
                 }
+                "execveat" if event.result()?.is_failed() => file_access_event(event, &paths)?,
                 "execveat" => {
                     let args = event.args()?;
 
-                    let dir = args
-                        .value_at_index(0)
-                        .and_then(super::Value::to_bstring)
-                        .map(Cow::into_owned);
-                    let command = args
-                        .value_at_index(1)
-                        .and_then(super::Value::to_bstring)
-                        .map(Cow::into_owned);
-                    let command = match (dir, command) {
-                        (Some(mut dir), Some(command)) => {
-                            if !command.is_empty() {
-                                dir.push(b'/');
-                                dir.extend_from_slice(&command);
-                            }
-                            Some(dir)
-                        }
-                        (Some(path), None) | (None, Some(path)) => Some(path),
-                        (None, None) => None,
-                    };
+                    // The path is resolved relative to the dir fd
+                    let command = paths.first().cloned().flatten();
 
                     let exec_args =
                         args.value_at_index(2)
@@ -130,9 +163,50 @@ impl Analyzer {
                         },
                     )
                 }
+                "open" | "openat" | "openat2" | "creat" | "socket" | "socketpair" | "accept"
+                | "accept4" | "pipe" | "pipe2" | "dup" | "dup2" | "dup3" | "fcntl" | "fcntl64"
+                | "close" | "close_range" => {
+                    self.handle_fd_syscall(line.pid, event, &paths)?;
+
+                    match event.name {
+                        "open" | "openat" | "openat2" | "creat" => {
+                            file_access_event(event, &paths)?
+                        }
+                        _ => EventKind::Log,
+                    }
+                }
+                "stat" | "lstat" | "stat64" | "lstat64" | "oldstat" | "oldlstat" | "newfstatat"
+                | "fstatat64" | "statx" | "access" | "faccessat" | "faccessat2" | "statfs"
+                | "statfs64" | "readlink" | "readlinkat" | "unlink" | "unlinkat" | "rmdir"
+                | "rename" | "renameat" | "renameat2" | "link" | "linkat" | "symlink"
+                | "symlinkat" | "mkdir" | "mkdirat" | "mknod" | "mknodat" | "truncate"
+                | "truncate64" | "chmod" | "fchmodat" | "fchmodat2" | "chown" | "lchown"
+                | "chown32" | "lchown32" | "fchownat" | "utime" | "utimes" | "utimensat"
+                | "futimesat" | "getxattr" | "lgetxattr" | "setxattr" | "lsetxattr"
+                | "listxattr" | "llistxattr" | "removexattr" | "lremovexattr" => {
+                    file_access_event(event, &paths)?
+                }
+                "chdir" | "fchdir" => {
+                    self.handle_chdir(line.pid, event, &paths)?;
+                    EventKind::Log
+                }
+                "wait4" | "waitpid" | "waitid" => self.handle_wait(&line, event)?,
+                "kill" | "tkill" | "tgkill" => {
+                    self.handle_kill(&line, event)?;
+                    EventKind::Log
+                }
+                "exit_group" => {
+                    let args = event.args()?;
+                    let stopped = ProcessStoppedReason::Exited {
+                        code: args.value_at_index(0).and_then(super::Value::as_i32),
+                    };
+                    self.handle_exit_group(&line, stopped)
+                }
                 _ => EventKind::Log,
             },
-            super::Event::Signal { .. } => EventKind::Log,
+            super::Event::Unfinished(_) | super::Event::Resumed(_) => EventKind::Log,
+            super::Event::Signal { signal } => self.handle_signal(&line, signal),
+            super::Event::GroupStop { signal } => self.handle_group_stop(&line, signal),
             super::Event::Exited(event) => {
                 let code = event.code()?;
                 let stopped = ProcessStoppedReason::Exited {
@@ -147,66 +221,914 @@ impl Analyzer {
                 };
                 self.handle_stopped(&line, stopped)
             }
+            super::Event::Superseded { thread_pid } => {
+                self.handle_superseded(&line, *thread_pid);
+                EventKind::Log
+            }
         };
 
         let process_state = self.processes.get(&line.pid);
+        let open_fds = process_state
+            .and_then(|state| self.fd_tables.get(&state.fd_table))
+            .map(|fd_table| fd_table.fds.len());
 
         Ok(Event {
             kind,
+            tgid: process_state.map_or(line.pid, |state| state.tgid),
             owner_pid: process_state.and_then(|state| state.owner_pid),
             parent_pid: process_state.and_then(|state| state.parent_pid),
             pid: line.pid,
             timestamp: line.timestamp,
+            paths,
+            fd_path,
+            open_fds,
             strace: line,
         })
     }
 
-    fn handle_fork(&mut self, strace: &super::Line, child_pid: Pid) -> EventKind {
+    /// Get the file descriptor `fd` from a process's fd table, if it's
+    /// currently open.
+    pub fn file_descriptor(&self, pid: Pid, fd: i32) -> Option<&FileDescriptor> {
+        let process_state = self.processes.get(&pid)?;
+        let fd_table = self.fd_tables.get(&process_state.fd_table)?;
+        fd_table.fds.get(&fd)
+    }
+
+    /// Get the thread group id of a process or thread, if it's been seen.
+    pub fn tgid(&self, pid: Pid) -> Option<Pid> {
+        let process_state = self.processes.get(&pid)?;
+        Some(process_state.tgid)
+    }
+
+    /// Get the current working directory of a process, if it's known.
+    pub fn cwd(&self, pid: Pid) -> Option<&bstr::BStr> {
+        let process_state = self.processes.get(&pid)?;
+        let fs_state = self.fs_states.get(&process_state.fs)?;
+        let cwd = fs_state.cwd.as_ref()?;
+        Some(cwd.as_bstr())
+    }
+
+    fn handle_fork(
+        &mut self,
+        strace: &super::Line,
+        child_pid: Pid,
+        clone_flags: CloneFlags,
+    ) -> EventKind {
         let child_owner_pid = self.find_owner_pid(strace.pid);
-        let child_process_state = self
+        let parent_state = *self.process_state_mut(strace.pid);
+
+        // Threads join the parent's thread group and share its parent,
+        // otherwise the child starts a new thread group
+        let (child_tgid, child_parent_pid) = if clone_flags.thread {
+            (parent_state.tgid, parent_state.parent_pid)
+        } else {
+            (child_pid, Some(parent_state.tgid))
+        };
+
+        // The child either shares the parent's fd table (`CLONE_FILES`) or
+        // gets a copy of it
+        let child_fd_table = if clone_flags.files {
+            self.retain_shared_state(parent_state.fd_table);
+            parent_state.fd_table
+        } else {
+            let fd_table = self
+                .fd_tables
+                .get(&parent_state.fd_table)
+                .cloned()
+                .unwrap_or_default();
+            self.insert_fd_table(fd_table)
+        };
+
+        // Same for the cwd (`CLONE_FS`)
+        let child_fs = if clone_flags.fs {
+            self.retain_shared_state(parent_state.fs);
+            parent_state.fs
+        } else {
+            let fs_state = self
+                .fs_states
+                .get(&parent_state.fs)
+                .cloned()
+                .unwrap_or_default();
+            self.insert_fs_state(fs_state)
+        };
+
+        // The pid was reused after an earlier process stopped
+        if self
             .processes
-            .entry(child_pid)
-            .or_insert_with(|| ProcessState {
-                parent_pid: Some(strace.pid),
+            .get(&child_pid)
+            .is_some_and(|state| matches!(state.status, ProcessStatus::Stopped))
+        {
+            self.processes.remove(&child_pid);
+        }
+
+        let child_process_state = match self.processes.get(&child_pid).copied() {
+            None => ProcessState {
+                tgid: child_tgid,
+                parent_pid: child_parent_pid,
                 owner_pid: child_owner_pid,
                 status: ProcessStatus::Forked,
-            });
+                suspended_at: None,
+                exited_at: None,
+                fd_table: child_fd_table,
+                fs: child_fs,
+            },
+            Some(existing_state) => {
+                // The child was already seen (its first lines came before
+                // the `clone` returned in the parent), so its state was
+                // created without knowing the clone flags
+                if let Some(threads) = self.running_threads.get_mut(&existing_state.tgid) {
+                    threads.remove(&child_pid);
+                    if threads.is_empty() {
+                        self.running_threads.remove(&existing_state.tgid);
+                    }
+                }
+
+                let (fd_table, fs) =
+                    self.apply_clone_flags(existing_state, child_fd_table, child_fs, clone_flags);
+                ProcessState {
+                    tgid: child_tgid,
+                    parent_pid: child_parent_pid,
+                    owner_pid: existing_state.owner_pid.or(child_owner_pid),
+                    fd_table,
+                    fs,
+                    ..existing_state
+                }
+            }
+        };
+        self.processes.insert(child_pid, child_process_state);
+        self.running_threads
+            .entry(child_process_state.tgid)
+            .or_default()
+            .insert(child_pid);
+        let child_owner_pid = child_process_state.owner_pid;
 
         EventKind::ForkProcess(ForkProcessEvent {
             child_pid,
-            child_owner_pid: child_process_state.owner_pid,
+            child_owner_pid,
+            child_tgid: child_process_state.tgid,
+            thread: child_process_state.tgid != child_pid,
+            shared_memory: clone_flags.vm,
         })
     }
 
+    /// Pick the fd table and fs state for a child that was already seen
+    /// before the `clone` that created it returned. `fd_table` and `fs` are
+    /// the ones the child was cloned with (either the parent's or copies of
+    /// them). Shared ones replace the child's own, and copies get any
+    /// changes the child already made to its own.
+    fn apply_clone_flags(
+        &mut self,
+        existing_state: ProcessState,
+        fd_table: SharedStateId,
+        fs: SharedStateId,
+        clone_flags: CloneFlags,
+    ) -> (SharedStateId, SharedStateId) {
+        let execed = matches!(existing_state.status, ProcessStatus::Execed);
+        let fd_table = if clone_flags.files && execed {
+            // `exec` already unshared the child's fd table
+            self.release_fd_table(fd_table);
+            existing_state.fd_table
+        } else if clone_flags.files {
+            self.release_fd_table(existing_state.fd_table);
+            fd_table
+        } else {
+            let existing_fds = self
+                .fd_tables
+                .get(&existing_state.fd_table)
+                .map(|fd_table| fd_table.fds.clone())
+                .unwrap_or_default();
+            if let Some(fd_table) = self.fd_tables.get_mut(&fd_table) {
+                if execed {
+                    fd_table.fds.retain(|_, fd| !fd.cloexec);
+                }
+                fd_table.fds.extend(existing_fds);
+            }
+            self.release_fd_table(existing_state.fd_table);
+            fd_table
+        };
+
+        let fs = if clone_flags.fs {
+            self.release_fs_state(existing_state.fs);
+            fs
+        } else {
+            let existing_cwd = self
+                .fs_states
+                .get(&existing_state.fs)
+                .and_then(|fs_state| fs_state.cwd.clone());
+            if let Some(cwd) = existing_cwd
+                && let Some(fs_state) = self.fs_states.get_mut(&fs)
+            {
+                fs_state.cwd = Some(cwd);
+            }
+            self.release_fs_state(existing_state.fs);
+            fs
+        };
+
+        (fd_table, fs)
+    }
+
     fn handle_exec(&mut self, strace: &super::Line, exec: ProcessExec) -> EventKind {
-        let process_state = self
-            .processes
-            .entry(strace.pid)
-            .or_insert_with(|| ProcessState {
-                parent_pid: None,
-                owner_pid: None,
-                status: ProcessStatus::Forked,
-            });
+        let process_state = self.process_state_mut(strace.pid);
 
         let re_exec = matches!(process_state.status, ProcessStatus::Execed);
         process_state.status = ProcessStatus::Execed;
 
+        // exec unshares the fd table, then closes all close-on-exec fds
+        let prev_fd_table = process_state.fd_table;
+        let mut fd_table = self
+            .fd_tables
+            .get(&prev_fd_table)
+            .cloned()
+            .unwrap_or_default();
+        fd_table.fds.retain(|_, fd| !fd.cloexec);
+        let fd_table = self.insert_fd_table(fd_table);
+        self.process_state_mut(strace.pid).fd_table = fd_table;
+        self.release_fd_table(prev_fd_table);
+
         EventKind::ExecProcess(ExecProcessEvent { exec, re_exec })
     }
 
     fn handle_stopped(&mut self, strace: &super::Line, stopped: ProcessStoppedReason) -> EventKind {
-        let process_state = self
-            .processes
-            .entry(strace.pid)
-            .or_insert_with(|| ProcessState {
-                parent_pid: None,
-                owner_pid: None,
-                status: ProcessStatus::Stopped,
-            });
+        let process_state = *self.process_state_mut(strace.pid);
+        if matches!(process_state.status, ProcessStatus::Stopped) {
+            // Already stopped by `exit_group`
+            return EventKind::Log;
+        }
+
         let did_exec = matches!(process_state.status, ProcessStatus::Execed);
+        self.stop_process(strace.pid, strace.timestamp);
+
+        EventKind::StopProcess(StopProcessEvent {
+            stopped,
+            did_exec,
+            thread: process_state.tgid != strace.pid,
+        })
+    }
+
+    /// Track when a child gets reaped by `wait4`, `waitpid`, or `waitid`.
+    fn handle_wait(
+        &mut self,
+        strace: &super::Line,
+        event: &super::SyscallEvent,
+    ) -> Result<EventKind, StraceParseError> {
+        let result = event.result()?;
+        let args = event.args()?;
+
+        let (child_pid, reaped) = match event.name {
+            "waitid" => {
+                // `waitid` returns 0, and fills in the child's siginfo
+                let info = args.value_at_index(2);
+                let child_pid = info
+                    .and_then(|info| info.field("si_pid"))
+                    .and_then(super::Value::as_i32);
+                let code = info.and_then(|info| info.field("si_code"));
+                let exited = code.is_some_and(|code| {
+                    ["CLD_EXITED", "CLD_KILLED", "CLD_DUMPED"]
+                        .iter()
+                        .any(|exit_code| code.has_flag(exit_code))
+                });
+                let no_wait = args
+                    .value_at_index(3)
+                    .is_some_and(|options| options.has_flag("WNOWAIT"));
+                let reaped = result.success_i32().is_some() && exited && !no_wait;
+                (child_pid, reaped)
+            }
+            _ => {
+                // Stopped and continued children are reported without
+                // being reaped (with `WUNTRACED` or `WCONTINUED`)
+                let status = args.value_at_index(1).and_then(wait_status_macro);
+                let reaped = !matches!(status, Some("WIFSTOPPED" | "WIFCONTINUED"));
+                (result.success_i32(), reaped)
+            }
+        };
+
+        // `WNOHANG` returns 0 if no child has changed state
+        let Some(child_pid) = child_pid.filter(|&child_pid| child_pid > 0) else {
+            return Ok(EventKind::Log);
+        };
+        if !reaped {
+            return Ok(EventKind::Log);
+        }
+
+        // The event starts when the wait started, so the child was reaped
+        // when the syscall returned
+        let reaped_at = jiff::SignedDuration::try_from(event.duration)
+            .ok()
+            .and_then(|duration| strace.timestamp.checked_add(duration).ok())
+            .unwrap_or(strace.timestamp);
+
+        let reaper_tgid = self.process_state_mut(strace.pid).tgid;
+
+        // Children that were never traced (e.g. ones started before strace
+        // attached) don't get tracked just to be reaped
+        let Some(child_state) = self.processes.get(&child_pid) else {
+            return Ok(EventKind::Log);
+        };
+        let orphan = child_state
+            .parent_pid
+            .is_some_and(|parent_pid| parent_pid != reaper_tgid);
+
+        Ok(EventKind::ReapProcess(ReapProcessEvent {
+            child_pid,
+            exited_at: child_state.exited_at,
+            reaped_at,
+            wait_duration: event.duration,
+            orphan,
+        }))
+    }
+
+    /// Keep track of a signal sent to another process, so it can be linked
+    /// to the signal once it's delivered.
+    fn handle_kill(
+        &mut self,
+        strace: &super::Line,
+        event: &super::SyscallEvent,
+    ) -> Result<(), StraceParseError> {
+        let result = event.result()?;
+        if result.success_i32().is_none() {
+            return Ok(());
+        }
+
+        let args = event.args()?;
+        let (target, signal) = match event.name {
+            "tgkill" => (args.value_at_index(1), args.value_at_index(2)),
+            _ => (args.value_at_index(0), args.value_at_index(1)),
+        };
+
+        // Signals sent to process groups (`kill(0, ...)` or `kill(-pgid, ...)`)
+        // can't be matched to a single process
+        let target = target.and_then(super::Value::as_i32);
+        let Some(target) = target.filter(|&target| target > 0) else {
+            return Ok(());
+        };
+        let Some(super::Value::Expression(signal)) = signal else {
+            return Ok(());
+        };
+
+        let owner_pid = self.process_state_mut(strace.pid).owner_pid;
+        self.sent_signals.insert(
+            (target, signal.to_string()),
+            SignalSyscall {
+                pid: strace.pid,
+                owner_pid,
+                timestamp: strace.timestamp,
+                syscall: event.name.to_string(),
+            },
+        );
+
+        Ok(())
+    }
+
+    fn handle_signal(&mut self, strace: &super::Line, signal: &str) -> EventKind {
+        let signal = self.parse_signal(strace, signal);
+
+        // `SIGCONT` continues a suspended process
+        let process_state = self.process_state_mut(strace.pid);
+        if signal.signal == "SIGCONT"
+            && let Some(suspended_at) = process_state.suspended_at.take()
+        {
+            return EventKind::ContinueProcess(ContinueProcessEvent {
+                suspended_at,
+                signal,
+            });
+        }
+
+        EventKind::Signal(signal)
+    }
+
+    /// Track when a process gets suspended, until it's continued by
+    /// `SIGCONT`.
+    fn handle_group_stop(&mut self, strace: &super::Line, signal: &str) -> EventKind {
+        let process_state = self.process_state_mut(strace.pid);
+        if process_state.suspended_at.is_some() {
+            return EventKind::Log;
+        }
+
+        process_state.suspended_at = Some(strace.timestamp);
+        EventKind::SuspendProcess(SuspendProcessEvent {
+            signal: signal.to_string(),
+        })
+    }
+
+    /// Parse a delivered signal, such as
+    /// `SIGTERM {si_signo=SIGTERM, si_code=SI_USER, si_pid=123, si_uid=1000}`.
+    /// The siginfo is left out if it can't be parsed.
+    fn parse_signal(&mut self, strace: &super::Line, signal: &str) -> SignalEvent {
+        let (name, siginfo) = signal.split_once(' ').unwrap_or((signal, ""));
+        let siginfo = super::parser::parse_whole_value(blame_on::Blame::new_str(siginfo)).ok();
+        let siginfo_expression = |field| match siginfo.as_ref()?.field(field)? {
+            super::Value::Expression(value) => Some(value.to_string()),
+            _ => None,
+        };
+
+        let code = siginfo_expression("si_code");
+        let status = siginfo_expression("si_status");
+        let sender_pid = siginfo
+            .as_ref()
+            .and_then(|siginfo| siginfo.field("si_pid"))
+            .and_then(super::Value::as_i32);
+
+        // Signals sent with `kill` can be delivered to any thread in the
+        // thread group
+        let tgid = self.process_state_mut(strace.pid).tgid;
+        let sent_by = [strace.pid, tgid]
+            .into_iter()
+            .find_map(|target| self.sent_signals.remove(&(target, name.to_string())));
+
+        SignalEvent {
+            signal: name.to_string(),
+            code,
+            sender_pid,
+            status,
+            sent_by,
+        }
+    }
+
+    /// Stop every thread in the thread group at once. Any later exit lines
+    /// from the threads are treated as logs.
+    fn handle_exit_group(
+        &mut self,
+        strace: &super::Line,
+        stopped: ProcessStoppedReason,
+    ) -> EventKind {
+        let tgid = self.process_state_mut(strace.pid).tgid;
+        let did_exec = self
+            .processes
+            .get(&tgid)
+            .is_some_and(|state| matches!(state.status, ProcessStatus::Execed));
+
+        for pid in self.running_threads(tgid) {
+            self.stop_process(pid, strace.timestamp);
+        }
+
+        EventKind::StopProcess(StopProcessEvent {
+            stopped,
+            did_exec,
+            thread: false,
+        })
+    }
+
+    /// Handle a thread calling `execve`, which makes it take over the
+    /// thread group leader's pid. The thread's state replaces the leader's,
+    /// and every other thread in the group gets killed.
+    fn handle_superseded(&mut self, strace: &super::Line, thread_pid: Option<Pid>) {
+        let leader_state = *self.process_state_mut(strace.pid);
+
+        for pid in self.running_threads(leader_state.tgid) {
+            if pid != strace.pid && Some(pid) != thread_pid {
+                self.stop_process(pid, strace.timestamp);
+            }
+        }
+
+        // Without the thread's pid (from older versions of strace), there's
+        // no way to tell which thread called `execve`, so the leader's state
+        // is kept as-is
+        let Some(thread_pid) = thread_pid else {
+            return;
+        };
+        let Some(thread_state) = self.processes.remove(&thread_pid) else {
+            return;
+        };
+
+        if let Some(threads) = self.running_threads.get_mut(&leader_state.tgid) {
+            threads.remove(&thread_pid);
+        }
+
+        self.processes.insert(
+            strace.pid,
+            ProcessState {
+                fd_table: thread_state.fd_table,
+                fs: thread_state.fs,
+                ..leader_state
+            },
+        );
+
+        // The leader's state was already released if it stopped before the
+        // thread called `execve`
+        if !matches!(leader_state.status, ProcessStatus::Stopped) {
+            self.release_fd_table(leader_state.fd_table);
+            self.release_fs_state(leader_state.fs);
+        }
+    }
+
+    /// Update the process's fd table after a syscall that opens, closes,
+    /// or duplicates file descriptors.
+    fn handle_fd_syscall(
+        &mut self,
+        pid: Pid,
+        event: &super::SyscallEvent,
+        paths: &[Option<bstr::BString>],
+    ) -> Result<(), StraceParseError> {
+        let args = event.args()?;
+        let result = event.result()?;
+
+        let fd_table_id = self.process_state_mut(pid).fd_table;
+        let fd_table = self.fd_tables.entry(fd_table_id).or_default();
+
+        match event.name {
+            "open" | "openat" | "openat2" | "creat" => {
+                let Some(fd) = result.success_i32() else {
+                    return Ok(());
+                };
+
+                let flags = match event.name {
+                    "open" => args.value_at_index(1),
+                    "openat" => args.value_at_index(2),
+                    "openat2" => args
+                        .value_at_index(2)
+                        .and_then(|open_how| open_how.field("flags")),
+                    _ => None,
+                };
+
+                // Prefer the path decoded by strace (`--decode-fds`), which
+                // has symlinks resolved
+                let path = result
+                    .returned
+                    .as_ref()
+                    .and_then(super::Value::annotation)
+                    .map(bstr::BString::from)
+                    .or_else(|| paths.first().cloned().flatten());
+                let cloexec = flags.is_some_and(|flags| flags.has_flag("O_CLOEXEC"));
+
+                fd_table.fds.insert(
+                    fd,
+                    FileDescriptor {
+                        kind: FileDescriptorKind::File,
+                        path,
+                        cloexec,
+                    },
+                );
+            }
+            "socket" | "accept" | "accept4" => {
+                let Some(fd) = result.success_i32() else {
+                    return Ok(());
+                };
+
+                let flags = match event.name {
+                    "socket" => args.value_at_index(1),
+                    "accept4" => args.value_at_index(3),
+                    _ => None,
+                };
+                let cloexec = flags.is_some_and(|flags| flags.has_flag("SOCK_CLOEXEC"));
+
+                fd_table.fds.insert(
+                    fd,
+                    FileDescriptor {
+                        kind: FileDescriptorKind::Socket,
+                        path: annotated_path(result.returned.as_ref()),
+                        cloexec,
+                    },
+                );
+            }
+            "socketpair" | "pipe" | "pipe2" => {
+                if result.success_i32().is_none() {
+                    return Ok(());
+                }
+
+                let (kind, fds, cloexec) = match event.name {
+                    "socketpair" => (
+                        FileDescriptorKind::Socket,
+                        args.value_at_index(3),
+                        args.value_at_index(1)
+                            .is_some_and(|flags| flags.has_flag("SOCK_CLOEXEC")),
+                    ),
+                    _ => (
+                        FileDescriptorKind::Pipe,
+                        args.value_at_index(0),
+                        args.value_at_index(1)
+                            .is_some_and(|flags| flags.has_flag("O_CLOEXEC")),
+                    ),
+                };
+
+                for fd_value in fds.and_then(super::Value::as_array).unwrap_or_default() {
+                    let Some(fd) = fd_value.as_i32() else {
+                        continue;
+                    };
+
+                    fd_table.fds.insert(
+                        fd,
+                        FileDescriptor {
+                            kind,
+                            path: annotated_path(Some(fd_value)),
+                            cloexec,
+                        },
+                    );
+                }
+            }
+            "dup" | "dup2" | "dup3" => {
+                let Some(new_fd) = result.success_i32() else {
+                    return Ok(());
+                };
+                let old_fd = args.value_at_index(0).and_then(super::Value::as_i32);
+                let cloexec = event.name == "dup3"
+                    && args
+                        .value_at_index(2)
+                        .is_some_and(|flags| flags.has_flag("O_CLOEXEC"));
+
+                fd_table.duplicate(old_fd, new_fd, cloexec);
+            }
+            "fcntl" | "fcntl64" => {
+                let fd = args.value_at_index(0).and_then(super::Value::as_i32);
+                let command = args.value_at_index(1);
+
+                if command.is_some_and(|command| command.has_flag("F_DUPFD")) {
+                    if let Some(new_fd) = result.success_i32() {
+                        fd_table.duplicate(fd, new_fd, false);
+                    }
+                } else if command.is_some_and(|command| command.has_flag("F_DUPFD_CLOEXEC")) {
+                    if let Some(new_fd) = result.success_i32() {
+                        fd_table.duplicate(fd, new_fd, true);
+                    }
+                } else if command.is_some_and(|command| command.has_flag("F_SETFD"))
+                    && result.success_i32().is_some()
+                    && let Some(fd) = fd.and_then(|fd| fd_table.fds.get_mut(&fd))
+                {
+                    fd.cloexec = args
+                        .value_at_index(2)
+                        .is_some_and(|flags| flags.has_flag("FD_CLOEXEC"));
+                }
+            }
+            "close" => {
+                // The fd is released even if `close` returns an error
+                if let Some(fd) = args.value_at_index(0).and_then(super::Value::as_i32) {
+                    fd_table.fds.remove(&fd);
+                }
+            }
+            "close_range" => {
+                if result.success_i32().is_none() {
+                    return Ok(());
+                }
+
+                let first = args
+                    .value_at_index(0)
+                    .and_then(super::Value::as_i32)
+                    .unwrap_or(0);
+                let last = args
+                    .value_at_index(1)
+                    .and_then(super::Value::as_i32)
+                    .unwrap_or(i32::MAX);
+                let flags = args.value_at_index(2);
+                let range = first..=last;
+
+                if flags.is_some_and(|flags| flags.has_flag("CLOSE_RANGE_CLOEXEC")) {
+                    for (_, fd) in fd_table
+                        .fds
+                        .iter_mut()
+                        .filter(|(fd, _)| range.contains(*fd))
+                    {
+                        fd.cloexec = true;
+                    }
+                } else {
+                    fd_table.fds.retain(|fd, _| !range.contains(fd));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_chdir(
+        &mut self,
+        pid: Pid,
+        event: &super::SyscallEvent,
+        paths: &[Option<bstr::BString>],
+    ) -> Result<(), StraceParseError> {
+        let result = event.result()?;
+        if result.success_i32().is_none() {
+            return Ok(());
+        }
+
+        let cwd = match event.name {
+            "fchdir" => {
+                let args = event.args()?;
+                let fd = args.value_at_index(0);
+                fd.and_then(super::Value::annotation)
+                    .map(bstr::BString::from)
+                    .or_else(|| {
+                        let fd = fd.and_then(super::Value::as_i32)?;
+                        self.file_descriptor(pid, fd)?.path.clone()
+                    })
+            }
+            _ => paths.first().cloned().flatten(),
+        };
+
+        let fs = self.process_state_mut(pid).fs;
+        self.fs_states.entry(fs).or_default().cwd = cwd;
+
+        Ok(())
+    }
+
+    /// Resolve each path argument of a syscall to an absolute path, relative
+    /// to either the cwd or the syscall's dir fd. Paths are left as-is if
+    /// the directory isn't known. Arguments that aren't strings (such as
+    /// `NULL`) are kept as `None`, so each path stays at its index.
+    fn resolve_path_args(
+        &mut self,
+        pid: Pid,
+        event: &super::SyscallEvent,
+    ) -> Result<Vec<Option<bstr::BString>>, StraceParseError> {
+        let path_args = path_arg_indices(event.name);
+        if path_args.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let args = event.args()?;
+        let paths = path_args
+            .iter()
+            .map(|&(dir_fd_index, path_index)| {
+                let path = args.value_at_index(path_index)?.to_bstring()?;
+                let dir_fd = dir_fd_index.and_then(|index| args.value_at_index(index));
+                Some(self.resolve_path(pid, dir_fd, &path))
+            })
+            .collect();
+        Ok(paths)
+    }
+
+    /// Get the path of the fd a syscall reads from or writes to, either
+    /// from the fd decoded by strace (`--decode-fds`) or from the process's
+    /// fd table.
+    fn resolve_fd_path(
+        &self,
+        pid: Pid,
+        event: &super::SyscallEvent,
+    ) -> Result<Option<bstr::BString>, StraceParseError> {
+        if !matches!(
+            event.name,
+            "read"
+                | "pread64"
+                | "readv"
+                | "preadv"
+                | "preadv2"
+                | "recvfrom"
+                | "recvmsg"
+                | "write"
+                | "pwrite64"
+                | "writev"
+                | "pwritev"
+                | "pwritev2"
+                | "sendto"
+                | "sendmsg"
+        ) {
+            return Ok(None);
+        }
+
+        let args = event.args()?;
+        let Some(fd) = args.value_at_index(0) else {
+            return Ok(None);
+        };
+        let path = fd.annotation().map(bstr::BString::from).or_else(|| {
+            let fd = fd.as_i32()?;
+            self.file_descriptor(pid, fd)?.path.clone()
+        });
+        Ok(path)
+    }
+
+    fn resolve_path(
+        &mut self,
+        pid: Pid,
+        dir_fd: Option<&super::Value>,
+        path: &bstr::BStr,
+    ) -> bstr::BString {
+        if path.starts_with(b"/") {
+            return path.into();
+        }
+
+        let dir = match dir_fd.and_then(super::Value::as_i32) {
+            Some(fd) => dir_fd
+                .and_then(super::Value::annotation)
+                .map(bstr::BString::from)
+                .or_else(|| self.file_descriptor(pid, fd)?.path.clone()),
+            None => {
+                // strace annotates `AT_FDCWD` with the cwd when decoding
+                // fds, so use it to learn the cwd
+                if let Some(cwd) = dir_fd.and_then(super::Value::annotation) {
+                    let fs = self.process_state_mut(pid).fs;
+                    self.fs_states.entry(fs).or_default().cwd = Some(cwd.into());
+                }
+
+                self.cwd(pid).map(bstr::BString::from)
+            }
+        };
+
+        match dir {
+            Some(dir) => join_paths(&dir, path),
+            None => path.into(),
+        }
+    }
+
+    /// Get the state of a process, or start tracking it if it hasn't been
+    /// seen before.
+    fn process_state_mut(&mut self, pid: Pid) -> &mut ProcessState {
+        if !self.processes.contains_key(&pid) {
+            let fd_table = self.insert_fd_table(FdTable::default());
+            let fs = self.insert_fs_state(FsState::default());
+            self.processes.insert(
+                pid,
+                ProcessState {
+                    tgid: pid,
+                    parent_pid: None,
+                    owner_pid: None,
+                    status: ProcessStatus::Forked,
+                    suspended_at: None,
+                    exited_at: None,
+                    fd_table,
+                    fs,
+                },
+            );
+            self.running_threads.entry(pid).or_default().insert(pid);
+        }
+
+        self.processes.get_mut(&pid).unwrap()
+    }
+
+    /// Mark a process as stopped, and drop any state no other process uses.
+    fn stop_process(&mut self, pid: Pid, timestamp: jiff::Timestamp) {
+        let process_state = self.process_state_mut(pid);
         process_state.status = ProcessStatus::Stopped;
+        process_state.suspended_at = None;
+        process_state.exited_at = Some(timestamp);
+
+        let process_state = *process_state;
+        self.release_fd_table(process_state.fd_table);
+        self.release_fs_state(process_state.fs);
+
+        if let Some(threads) = self.running_threads.get_mut(&process_state.tgid) {
+            threads.remove(&pid);
+            if threads.is_empty() {
+                self.running_threads.remove(&process_state.tgid);
+            }
+        }
 
-        EventKind::StopProcess(StopProcessEvent { stopped, did_exec })
+        // Signals sent to the process can't be delivered anymore
+        self.sent_signals.retain(|(target, _), _| *target != pid);
+    }
+
+    /// Get the pids of each thread in a thread group that hasn't stopped,
+    /// including the leader, ordered by pid.
+    fn running_threads(&self, tgid: Pid) -> Vec<Pid> {
+        self.running_threads
+            .get(&tgid)
+            .map(|threads| threads.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    fn next_shared_state_id(&mut self) -> SharedStateId {
+        let id = SharedStateId(self.next_shared_state_id);
+        self.next_shared_state_id += 1;
+        id
+    }
+
+    /// Add a new fd table, used by one process.
+    fn insert_fd_table(&mut self, fd_table: FdTable) -> SharedStateId {
+        let fd_table_id = self.next_shared_state_id();
+        self.fd_tables.insert(fd_table_id, fd_table);
+        self.shared_state_refs.insert(fd_table_id, 1);
+        fd_table_id
+    }
+
+    /// Add a new filesystem state, used by one process.
+    fn insert_fs_state(&mut self, fs_state: FsState) -> SharedStateId {
+        let fs_id = self.next_shared_state_id();
+        self.fs_states.insert(fs_id, fs_state);
+        self.shared_state_refs.insert(fs_id, 1);
+        fs_id
+    }
+
+    /// Track another process using an fd table or filesystem state.
+    fn retain_shared_state(&mut self, id: SharedStateId) {
+        *self.shared_state_refs.entry(id).or_default() += 1;
+    }
+
+    /// Track a process no longer using an fd table or filesystem state.
+    /// Returns true if no running process uses it anymore.
+    fn release_shared_state(&mut self, id: SharedStateId) -> bool {
+        let Some(refs) = self.shared_state_refs.get_mut(&id) else {
+            return true;
+        };
+
+        *refs = refs.saturating_sub(1);
+        if *refs > 0 {
+            return false;
+        }
+
+        self.shared_state_refs.remove(&id);
+        true
+    }
+
+    /// Drop an fd table once no running process uses it anymore.
+    fn release_fd_table(&mut self, fd_table_id: SharedStateId) {
+        if self.release_shared_state(fd_table_id) {
+            self.fd_tables.remove(&fd_table_id);
+        }
+    }
+
+    /// Drop a process's filesystem state once no running process uses it
+    /// anymore.
+    fn release_fs_state(&mut self, fs_id: SharedStateId) {
+        if self.release_shared_state(fs_id) {
+            self.fs_states.remove(&fs_id);
+        }
     }
 
     fn find_owner_pid(&self, mut pid: Pid) -> Option<Pid> {
@@ -219,6 +1141,12 @@ impl Analyzer {
                 break Some(pid);
             }
 
+            // Threads are owned by their thread group leader
+            if process_state.tgid != pid {
+                pid = process_state.tgid;
+                continue;
+            }
+
             let Some(parent_pid) = process_state.parent_pid else {
                 break None;
             };
@@ -229,9 +1157,19 @@ impl Analyzer {
 
 #[derive(Debug, Clone, Copy)]
 struct ProcessState {
+    tgid: Pid,
     parent_pid: Option<Pid>,
     owner_pid: Option<Pid>,
     status: ProcessStatus,
+
+    /// When the process was suspended by a job-control signal, if it
+    /// hasn't been continued since.
+    suspended_at: Option<jiff::Timestamp>,
+
+    /// When the process exited, if it has.
+    exited_at: Option<jiff::Timestamp>,
+    fd_table: SharedStateId,
+    fs: SharedStateId,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -240,3 +1178,233 @@ enum ProcessStatus {
     Execed,
     Stopped,
 }
+
+/// The flags from `clone` (or `clone3`) that change how the child relates
+/// to its parent.
+#[derive(Debug, Clone, Copy)]
+struct CloneFlags {
+    /// `CLONE_THREAD`: the child is a thread in the parent's thread group.
+    thread: bool,
+
+    /// `CLONE_VM`: the child shares the parent's memory.
+    vm: bool,
+
+    /// `CLONE_FILES`: the child shares the parent's fd table.
+    files: bool,
+
+    /// `CLONE_FS`: the child shares the parent's cwd.
+    fs: bool,
+}
+
+/// Identifies state that can be shared between processes, like fd tables
+/// and filesystem state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct SharedStateId(u64);
+
+/// The filesystem state of a process. Can be shared between processes
+/// created with `CLONE_FS` (such as threads).
+#[derive(Debug, Clone, Default)]
+struct FsState {
+    cwd: Option<bstr::BString>,
+}
+
+/// The open file descriptors of a process. Can be shared between processes
+/// created with `CLONE_FILES` (such as threads).
+#[derive(Debug, Clone, Default)]
+struct FdTable {
+    fds: HashMap<i32, FileDescriptor>,
+}
+
+impl FdTable {
+    fn duplicate(&mut self, old_fd: Option<i32>, new_fd: i32, cloexec: bool) {
+        let old = old_fd.and_then(|old_fd| self.fds.get(&old_fd));
+        let new = FileDescriptor {
+            kind: old.map_or(FileDescriptorKind::Unknown, |old| old.kind),
+            path: old.and_then(|old| old.path.clone()),
+            cloexec,
+        };
+        self.fds.insert(new_fd, new);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDescriptor {
+    pub kind: FileDescriptorKind,
+
+    /// The path the file descriptor was opened with. Only absolute if
+    /// the path was absolute or if the trace decoded fds (`--decode-fds`).
+    /// For pipes and sockets, this is the description decoded by strace
+    /// (e.g. `pipe:[1234]`), if any.
+    pub path: Option<bstr::BString>,
+
+    /// Whether the fd will be closed on exec (`O_CLOEXEC`).
+    pub cloexec: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDescriptorKind {
+    File,
+    Pipe,
+    Socket,
+    Unknown,
+}
+
+/// Get the macro a wait status starts with, such as `WIFEXITED` for
+/// `[{WIFEXITED(s) && WEXITSTATUS(s) == 0}]`.
+fn wait_status_macro<'a>(value: &'a super::Value) -> Option<&'a str> {
+    match value {
+        super::Value::Array(values) => wait_status_macro(values.first()?),
+        super::Value::Struct(fields) => wait_status_macro(&fields.first()?.value),
+        super::Value::BinaryOperations { first, .. } => wait_status_macro(first),
+        super::Value::FunctionCall { function, .. } => Some(function),
+        _ => None,
+    }
+}
+
+fn annotated_path(value: Option<&super::Value>) -> Option<bstr::BString> {
+    value
+        .and_then(super::Value::annotation)
+        .map(bstr::BString::from)
+}
+
+/// Build a file access event for a syscall with path arguments, using the
+/// resolved paths.
+fn file_access_event(
+    event: &super::SyscallEvent,
+    paths: &[Option<bstr::BString>],
+) -> Result<EventKind, StraceParseError> {
+    let args = event.args()?;
+    let result = event.result()?;
+
+    let flags = match event.name {
+        "open" => args.value_at_index(1),
+        "openat" => args.value_at_index(2),
+        "openat2" => args
+            .value_at_index(2)
+            .and_then(|open_how| open_how.field("flags")),
+        _ => None,
+    };
+
+    // The access modes for the path arguments, along with the index of
+    // each path. A path can be accessed in more than one way (e.g. a file
+    // opened with `O_RDWR` is both read and written)
+    let modes: Vec<(usize, FileAccessMode)> = match event.name {
+        "open" | "openat" | "openat2" => {
+            open_access_modes(|flag| flags.is_some_and(|flags| flags.has_flag(flag)))
+                .into_iter()
+                .map(|mode| (0, mode))
+                .collect()
+        }
+        "creat" | "mkdir" | "mkdirat" | "mknod" | "mknodat" | "symlink" | "symlinkat" => {
+            vec![(0, FileAccessMode::Create)]
+        }
+        "link" | "linkat" => vec![(0, FileAccessMode::Metadata), (1, FileAccessMode::Create)],
+        "rename" | "renameat" | "renameat2" => {
+            vec![(0, FileAccessMode::Delete), (1, FileAccessMode::Create)]
+        }
+        "unlink" | "unlinkat" | "rmdir" => vec![(0, FileAccessMode::Delete)],
+        "truncate" | "truncate64" => vec![(0, FileAccessMode::Write)],
+        "readlink" | "readlinkat" | "execve" | "execveat" => vec![(0, FileAccessMode::Read)],
+        _ => vec![(0, FileAccessMode::Metadata)],
+    };
+
+    let accesses: Vec<_> = modes
+        .into_iter()
+        .filter_map(|(index, mode)| {
+            let path = paths.get(index)?.as_ref()?;
+            Some(FileAccess {
+                path: path.clone(),
+                mode,
+            })
+        })
+        .collect();
+    if accesses.is_empty() {
+        return Ok(EventKind::Log);
+    }
+
+    let flags = flags.and_then(|flags| match flags {
+        super::Value::Expression(flags) => Some(flags.to_string()),
+        _ => None,
+    });
+    let error = result
+        .error
+        .and_then(|error| error.name)
+        .map(|name| name.to_string());
+
+    Ok(EventKind::FileAccess(FileAccessEvent {
+        accesses,
+        flags,
+        success: !result.is_failed(),
+        error,
+    }))
+}
+
+/// Get the access modes for a file opened with `open` or similar, given a
+/// function to check its open flags. A file opened with `O_CREAT` but not
+/// `O_EXCL` may have already existed, so it counts as written (or read,
+/// based on its access mode) as well as created.
+fn open_access_modes(has_flag: impl Fn(&str) -> bool) -> Vec<FileAccessMode> {
+    if has_flag("O_PATH") {
+        return vec![FileAccessMode::Metadata];
+    }
+
+    // The file is always new, so it was only created
+    if has_flag("O_TMPFILE") || (has_flag("O_CREAT") && has_flag("O_EXCL")) {
+        return vec![FileAccessMode::Create];
+    }
+
+    let mut modes = if has_flag("O_RDWR") {
+        vec![FileAccessMode::Read, FileAccessMode::Write]
+    } else if has_flag("O_WRONLY") || has_flag("O_TRUNC") {
+        vec![FileAccessMode::Write]
+    } else {
+        vec![FileAccessMode::Read]
+    };
+    if has_flag("O_CREAT") {
+        modes.push(FileAccessMode::Create);
+    }
+
+    modes
+}
+
+/// Join a relative path onto a directory, skipping empty and `.` components.
+/// `..` components are kept, since the directory could be a symlink.
+fn join_paths(dir: &bstr::BStr, path: &bstr::BStr) -> bstr::BString {
+    let mut joined = bstr::BString::from(dir);
+    for component in path.split_str("/") {
+        if component.is_empty() || component == b"." {
+            continue;
+        }
+
+        if !joined.ends_with(b"/") {
+            joined.push(b'/');
+        }
+        joined.extend_from_slice(component);
+    }
+
+    joined
+}
+
+/// Get the indices of the path arguments for a syscall, each along with the
+/// index of the dir fd it's relative to (paths without a dir fd are relative
+/// to the cwd).
+fn path_arg_indices(syscall_name: &str) -> &'static [(Option<usize>, usize)] {
+    match syscall_name {
+        "open" | "creat" | "stat" | "lstat" | "stat64" | "lstat64" | "oldstat" | "oldlstat"
+        | "access" | "readlink" | "unlink" | "rmdir" | "mkdir" | "mknod" | "chdir" | "chroot"
+        | "truncate" | "truncate64" | "chmod" | "chown" | "lchown" | "chown32" | "lchown32"
+        | "utime" | "utimes" | "statfs" | "statfs64" | "execve" | "uselib" | "acct"
+        | "getxattr" | "lgetxattr" | "setxattr" | "lsetxattr" | "listxattr" | "llistxattr"
+        | "removexattr" | "lremovexattr" => &[(None, 0)],
+        "rename" | "link" => &[(None, 0), (None, 1)],
+        "symlink" => &[(None, 1)],
+        "inotify_add_watch" => &[(None, 1)],
+        "openat" | "openat2" | "newfstatat" | "fstatat64" | "statx" | "faccessat"
+        | "faccessat2" | "readlinkat" | "unlinkat" | "mkdirat" | "mknodat" | "fchmodat"
+        | "fchmodat2" | "fchownat" | "utimensat" | "futimesat" | "execveat"
+        | "name_to_handle_at" => &[(Some(0), 1)],
+        "renameat" | "renameat2" | "linkat" => &[(Some(0), 1), (Some(2), 3)],
+        "symlinkat" => &[(Some(1), 2)],
+        _ => &[],
+    }
+}
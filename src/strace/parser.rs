@@ -5,30 +5,224 @@ use bstr::ByteVec as _;
 
 use crate::{
     Pid,
-    strace::{BinaryOperator, ExitedEvent, SyscallResult},
+    strace::{BinaryOperator, ExitedEvent, SyscallError, SyscallResult, errno},
 };
 
-use super::{Event, Field, Fields, Line, SyscallEvent, Value};
+use super::{
+    Event, Field, Fields, Line, ResumedSyscallEvent, SyscallEvent, UnfinishedSyscallEvent, Value,
+};
+
+/// Timestamps smaller than this many seconds are treated as relative
+/// timestamps when detecting the timestamp format (this is a little over 3
+/// years, so any recent Unix timestamp is much larger)
+const MIN_UNIX_TIMESTAMP_SECONDS: i64 = 100_000_000;
 
 pub fn parse_line<'a>(line: &'a str) -> Result<Line<'a>, StraceParseError> {
-    let input = Blame::new_str(line);
+    LineParser::default().parse_line(line)
+}
 
-    let (pid, input) = input
-        .split_once(" ")
-        .map_err(|blame| StraceParseError::new(blame.span, "expected pid"))?;
-    let pid = pid
-        .parse::<Pid>()
-        .map_err(|blame| StraceParseError::new(blame.span, "invalid pid"))?;
+/// The format of the timestamp column of strace output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// Detect the format of each timestamp
+    #[default]
+    Auto,
 
+    /// Seconds since the Unix epoch (`-ttt` or `--timestamps=unix`)
+    Unix,
+
+    /// Wall-clock time of day (`-t`, `-tt`, or `--timestamps=time`)
+    TimeOfDay,
+
+    /// Seconds since the previous line (`-r` or `--relative-timestamps`)
+    Relative,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ParserOptions {
+    pub timestamp_format: TimestampFormat,
+
+    /// The time used to resolve timestamps without a date. Time-of-day
+    /// timestamps use its date and time zone, and relative timestamps are
+    /// counted from it.
+    pub base_time: Option<jiff::Zoned>,
+
+    /// When set, lines are expected to have no pid column, and are all
+    /// attributed to this pid instead (e.g. when strace runs without `-f`,
+    /// or with `-ff -o prefix`).
+    pub pid: Option<Pid>,
+}
+
+/// Parses strace lines into [`Line`]s. Lines should be parsed in the order
+/// they appear in the strace output, since relative and time-of-day
+/// timestamps are resolved using the timestamps of previous lines.
+#[derive(Debug, Default, Clone)]
+pub struct LineParser {
+    options: ParserOptions,
+    previous_timestamp: Option<jiff::Timestamp>,
+    date: Option<jiff::civil::Date>,
+}
+
+impl LineParser {
+    pub fn new(options: ParserOptions) -> Self {
+        Self {
+            options,
+            previous_timestamp: None,
+            date: None,
+        }
+    }
+
+    pub fn parse_line<'a>(&mut self, line: &'a str) -> Result<Line<'a>, StraceParseError> {
+        let (pid, raw_timestamp, event) = parse_line_parts(line, &self.options)?;
+        let timestamp = self
+            .resolve_timestamp(raw_timestamp.value)
+            .map_err(|message| StraceParseError::new(raw_timestamp.span, message))?;
+
+        Ok(Line {
+            line,
+            pid,
+            timestamp,
+            event,
+        })
+    }
+
+    /// Parse a line using a timestamp that was already resolved for it
+    /// (such as a stitched line, which uses the timestamp of its unfinished
+    /// half), without affecting how later timestamps get resolved.
+    pub fn parse_line_at<'a>(
+        &self,
+        line: &'a str,
+        timestamp: jiff::Timestamp,
+    ) -> Result<Line<'a>, StraceParseError> {
+        let (pid, _, event) = parse_line_parts(line, &self.options)?;
+
+        Ok(Line {
+            line,
+            pid,
+            timestamp,
+            event,
+        })
+    }
+
+    fn resolve_timestamp(
+        &mut self,
+        raw_timestamp: RawTimestamp,
+    ) -> Result<jiff::Timestamp, &'static str> {
+        let timestamp = match raw_timestamp {
+            RawTimestamp::Unix(duration) => {
+                jiff::Timestamp::from_duration(duration).map_err(|_| "invalid timestamp")?
+            }
+            RawTimestamp::TimeOfDay(time) => {
+                let base_time = self
+                    .options
+                    .base_time
+                    .as_ref()
+                    .ok_or("time-of-day timestamps require a base date")?;
+                let time_zone = base_time.time_zone();
+                let date = self.date.unwrap_or_else(|| base_time.date());
+
+                let mut zoned = date
+                    .to_datetime(time)
+                    .to_zoned(time_zone.clone())
+                    .map_err(|_| "invalid timestamp")?;
+
+                // Assume we passed midnight if the time of day jumped
+                // backwards by more than 12 hours
+                if let Some(previous_timestamp) = self.previous_timestamp
+                    && previous_timestamp.duration_since(zoned.timestamp())
+                        > jiff::SignedDuration::from_hours(12)
+                {
+                    zoned = date
+                        .tomorrow()
+                        .and_then(|date| date.to_datetime(time).to_zoned(time_zone.clone()))
+                        .map_err(|_| "invalid timestamp")?;
+                }
+
+                self.date = Some(zoned.date());
+                zoned.timestamp()
+            }
+            RawTimestamp::Relative(duration) => {
+                let previous_timestamp = match self.previous_timestamp {
+                    Some(previous_timestamp) => previous_timestamp,
+                    None => self
+                        .options
+                        .base_time
+                        .as_ref()
+                        .ok_or("relative timestamps require a base time")?
+                        .timestamp(),
+                };
+                previous_timestamp
+                    .checked_add(duration)
+                    .map_err(|_| "invalid timestamp")?
+            }
+        };
+
+        self.previous_timestamp = Some(timestamp);
+        Ok(timestamp)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RawTimestamp {
+    Unix(jiff::SignedDuration),
+    TimeOfDay(jiff::civil::Time),
+    Relative(jiff::SignedDuration),
+}
+
+fn parse_raw_timestamp(timestamp: &str, format: TimestampFormat) -> Result<RawTimestamp, ()> {
+    match format {
+        TimestampFormat::Auto => {
+            if timestamp.contains(':') {
+                parse_raw_timestamp(timestamp, TimestampFormat::TimeOfDay)
+            } else {
+                let duration = parse_duration(timestamp)?;
+                if duration.as_secs() >= MIN_UNIX_TIMESTAMP_SECONDS {
+                    Ok(RawTimestamp::Unix(duration))
+                } else {
+                    Ok(RawTimestamp::Relative(duration))
+                }
+            }
+        }
+        TimestampFormat::Unix => {
+            let duration = parse_duration(timestamp)?;
+            Ok(RawTimestamp::Unix(duration))
+        }
+        TimestampFormat::TimeOfDay => {
+            let time = timestamp.parse().map_err(|_| ())?;
+            Ok(RawTimestamp::TimeOfDay(time))
+        }
+        TimestampFormat::Relative => {
+            let duration = parse_duration(timestamp)?;
+            Ok(RawTimestamp::Relative(duration))
+        }
+    }
+}
+
+fn parse_line_parts<'a>(
+    line: &'a str,
+    options: &ParserOptions,
+) -> Result<(Pid, Blame<RawTimestamp>, Event<'a>), StraceParseError> {
+    let input = Blame::new_str(line).trim_start();
+
+    let (pid, input) = if let Some(pid) = options.pid {
+        (pid, input)
+    } else {
+        let (pid, input) = input
+            .split_once(" ")
+            .map_err(|blame| StraceParseError::new(blame.span, "expected pid"))?;
+        let pid = pid
+            .parse::<Pid>()
+            .map_err(|blame| StraceParseError::new(blame.span, "invalid pid"))?;
+        (pid.value, input)
+    };
+
+    // Relative timestamps and short pids are padded with spaces
     let (timestamp, input) = input
+        .trim_start()
         .split_once(" ")
         .map_err(|blame| StraceParseError::new(blame.span, "expected timestamp"))?;
     let timestamp = timestamp
-        .try_map(|timestamp| {
-            let duration = parse_duration(timestamp)?;
-            let timestamp = jiff::Timestamp::from_duration(duration).map_err(|_| ())?;
-            Result::<_, ()>::Ok(timestamp)
-        })
+        .try_map(|timestamp| parse_raw_timestamp(timestamp, options.timestamp_format))
         .map_err(|blame| StraceParseError::new(blame.span, "invalid timestamp"))?;
 
     let event = if let Ok(input) = input.strip_prefix("+++ ") {
@@ -43,6 +237,15 @@ pub fn parse_line<'a>(line: &'a str) -> Result<Line<'a>, StraceParseError> {
             Event::Exited(ExitedEvent { code_string })
         } else if let Ok(signal_string) = event.strip_prefix("killed by ") {
             Event::KilledBy { signal_string }
+        } else if event.value == "superseded by execve" {
+            Event::Superseded { thread_pid: None }
+        } else if let Ok(thread_pid) = event.strip_prefix("superseded by execve in pid ") {
+            let thread_pid = thread_pid
+                .parse::<Pid>()
+                .map_err(|blame| StraceParseError::new(blame.span, "invalid pid"))?;
+            Event::Superseded {
+                thread_pid: Some(thread_pid.value),
+            }
         } else {
             return Err(StraceParseError::new(
                 event.span,
@@ -53,14 +256,63 @@ pub fn parse_line<'a>(line: &'a str) -> Result<Line<'a>, StraceParseError> {
         let signal = input
             .strip_suffix(" ---")
             .map_err(|blame| StraceParseError::new(blame.span, "failed to parse signal event"))?;
-        Event::Signal {
-            signal: signal.value,
+        if let Ok(signal) = signal.strip_prefix("stopped by ") {
+            Event::GroupStop {
+                signal: signal.value,
+            }
+        } else {
+            Event::Signal {
+                signal: signal.value,
+            }
         }
+    } else if let Ok(input) = input.strip_prefix("<... ") {
+        let (syscall_name, input) = input
+            .split_once(" resumed>")
+            .map_err(|blame| StraceParseError::new(blame.span, "failed to parse resumed event"))?;
+        let (args_string, result_string, duration) = parse_syscall_end(input)?;
+
+        Event::Resumed(ResumedSyscallEvent {
+            name: syscall_name.value,
+            args_string,
+            result_string,
+            duration,
+        })
+    } else if let Ok(input) = input.strip_suffix(" <unfinished ...>") {
+        let (syscall_name, args_string) = input
+            .split_once("(")
+            .map_err(|blame| StraceParseError::new(blame.span, "failed to parse event"))?;
+
+        Event::Unfinished(UnfinishedSyscallEvent {
+            name: syscall_name.value,
+            args_string,
+        })
     } else {
         let (syscall_name, input) = input
             .split_once("(")
             .map_err(|blame| StraceParseError::new(blame.span, "failed to parse event"))?;
+        let (args_string, result_string, duration) = parse_syscall_end(input)?;
+
+        Event::Syscall(SyscallEvent {
+            name: syscall_name.value,
+            args_string,
+            result_string,
+            duration,
+        })
+    };
+
+    Ok((pid, timestamp, event))
+}
 
+/// Parse the end of a syscall, starting just after the opening parenthesis
+/// (or just after `resumed>` for resumed syscalls). Returns the args string,
+/// the result string, and the syscall duration. Syscalls that never return
+/// (like `exit_group(0) = ?`) have no duration, so it's treated as zero.
+fn parse_syscall_end<'a>(
+    input: Blame<&'a str>,
+) -> Result<(Blame<&'a str>, Blame<&'a str>, std::time::Duration), StraceParseError> {
+    let (input, duration) = if input.value.trim_ascii_end().ends_with(" = ?") {
+        (input.trim_ascii_end(), std::time::Duration::ZERO)
+    } else {
         let (input, duration) = input
             .strip_suffix(">")
             .and_then(|input| input.rsplit_once(" <"))
@@ -74,28 +326,17 @@ pub fn parse_line<'a>(line: &'a str) -> Result<Line<'a>, StraceParseError> {
                 Result::<_, ()>::Ok(duration)
             })
             .map_err(|blame| StraceParseError::new(blame.span, "invalid duration"))?;
-        let (input, result_string) = input
-            .rsplit_once(" = ")
-            .map_err(|blame| StraceParseError::new(blame.span, "failed to parse syscall result"))?;
-        let args_string = input
-            .trim_ascii_end()
-            .strip_suffix(")")
-            .map_err(|blame| StraceParseError::new(blame.span, "failed to parse syscall args"))?;
-
-        Event::Syscall(SyscallEvent {
-            name: syscall_name.value,
-            args_string,
-            result_string: result_string.trim(),
-            duration: duration.value,
-        })
+        (input, duration.value)
     };
-
-    Ok(Line {
-        line,
-        pid: pid.value,
-        timestamp: timestamp.value,
-        event,
-    })
+    let (input, result_string) = input
+        .rsplit_once(" = ")
+        .map_err(|blame| StraceParseError::new(blame.span, "failed to parse syscall result"))?;
+    let args_string = input
+        .trim_ascii_end()
+        .strip_suffix(")")
+        .map_err(|blame| StraceParseError::new(blame.span, "failed to parse syscall args"))?;
+
+    Ok((args_string, result_string.trim(), duration))
 }
 
 pub(crate) fn parse_args<'a>(mut input: Blame<&'a str>) -> Result<Fields<'a>, StraceParseError> {
@@ -136,9 +377,55 @@ pub(crate) fn parse_syscall_result<'a>(
         (Some(value), rest)
     };
 
-    let message = rest.trim().non_empty().ok().map(|blame| blame.value);
+    let mut rest = rest.trim();
+
+    // Failed syscalls include the errno name after the value
+    // (e.g. `-1 ENOENT (No such file or directory)`)
+    let mut error = None;
+    if let Ok((name, after_name)) = parse_ident(rest)
+        && is_errno_name(name.value)
+    {
+        error = Some(SyscallError {
+            name: Some(name.value),
+            number: errno::errno_number(name.value),
+        });
+        rest = after_name.trim();
+    }
+
+    // Take the explanation from the surrounding parens, if any
+    let message = rest
+        .strip_prefix("(")
+        .and_then(|message| message.strip_suffix(")"))
+        .unwrap_or(rest)
+        .non_empty()
+        .ok()
+        .map(|blame| blame.value);
+
+    // Unknown errors don't have a name, but include the errno number in
+    // the message instead (e.g. `-1 (errno 1234)`)
+    if error.is_none()
+        && let Some(number) = message
+            .and_then(|message| message.strip_prefix("errno "))
+            .and_then(|number| number.parse().ok())
+    {
+        error = Some(SyscallError {
+            name: errno::errno_name(number),
+            number: Some(number),
+        });
+    }
+
+    Ok(SyscallResult {
+        returned,
+        error,
+        message,
+    })
+}
 
-    Ok(SyscallResult { returned, message })
+fn is_errno_name(value: &str) -> bool {
+    value.starts_with('E')
+        && value
+            .chars()
+            .all(|c| matches!(c, 'A'..='Z' | '0'..='9' | '_'))
 }
 
 fn parse_duration(s: &str) -> Result<jiff::SignedDuration, ()> {
@@ -691,7 +978,7 @@ fn is_ident(value: &str) -> bool {
 }
 
 fn is_basic_expression_char(c: char) -> bool {
-    matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '+' | '-' | '*' | '.' | '/' | '^' | '&' | '|')
+    matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '+' | '-' | '*' | '.' | '/' | '^' | '&' | '|' | '~')
 }
 
 fn parse_binary_op<'a>(
@@ -1036,6 +1323,7 @@ mod tests {
             parse_value("BLAH_BLAH_BLAH5").unwrap(),
             expr("BLAH_BLAH_BLAH5")
         );
+        assert_eq!(parse_value("~0U").unwrap(), expr("~0U"));
     }
 
     #[test]
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use crate::Pid;
+
+use super::{Event, Line};
+
+/// Joins `<unfinished ...>` lines with their matching `<... resumed>` lines,
+/// so each syscall can be analyzed as a single event.
+#[derive(Debug, Default)]
+pub struct Stitcher {
+    unfinished: HashMap<Pid, UnfinishedLine>,
+}
+
+impl Stitcher {
+    pub fn stitch(&mut self, line: &Line<'_>) -> Stitched {
+        match &line.event {
+            Event::Unfinished(unfinished) => {
+                let Some((head, _)) = line.line.rsplit_once(" <unfinished ...>") else {
+                    return Stitched::Complete;
+                };
+
+                // The pid and timestamp come before the syscall name
+                let call_start = head.find(&format!("{}(", unfinished.name)).unwrap_or(0);
+
+                self.unfinished.insert(
+                    line.pid,
+                    UnfinishedLine {
+                        name: unfinished.name.to_string(),
+                        head: head.to_string(),
+                        call_start,
+                        timestamp: line.timestamp,
+                        superseded: false,
+                    },
+                );
+                Stitched::Unfinished
+            }
+            Event::Resumed(resumed) => {
+                let Some((resumed_head, tail)) = line.line.split_once(" resumed>") else {
+                    return Stitched::Complete;
+                };
+
+                // Only join with the unfinished syscall if the names match.
+                // Otherwise, pass the resumed line through as-is
+                let Some(unfinished) = self.unfinished.remove(&line.pid) else {
+                    return Stitched::Complete;
+                };
+                if unfinished.name != resumed.name {
+                    return Stitched::Complete;
+                }
+
+                // If the syscall moved to another pid, keep the pid from the
+                // resumed line
+                let mut stitched_line = if unfinished.superseded {
+                    let (prefix, _) = resumed_head.rsplit_once("<... ").unwrap_or_default();
+                    let mut stitched_line = prefix.to_string();
+                    stitched_line.push_str(&unfinished.head[unfinished.call_start..]);
+                    stitched_line
+                } else {
+                    unfinished.head
+                };
+                stitched_line.push_str(tail);
+                Stitched::Resumed {
+                    line: stitched_line,
+                    timestamp: unfinished.timestamp,
+                }
+            }
+            Event::Exited(_) | Event::KilledBy { .. } => {
+                // The process won't resume any unfinished syscall
+                self.unfinished.remove(&line.pid);
+                Stitched::Complete
+            }
+            Event::Superseded { thread_pid } => {
+                // The thread that called `execve` takes over the leader's
+                // pid, so its `execve` gets resumed under the leader's pid
+                self.unfinished.remove(&line.pid);
+                if let Some(mut unfinished) =
+                    thread_pid.and_then(|thread_pid| self.unfinished.remove(&thread_pid))
+                {
+                    unfinished.superseded = true;
+                    self.unfinished.insert(line.pid, unfinished);
+                }
+                Stitched::Complete
+            }
+            Event::Syscall(_) | Event::Signal { .. } | Event::GroupStop { .. } => {
+                Stitched::Complete
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Stitched {
+    /// The line doesn't need to be joined with any other line.
+    Complete,
+
+    /// The line is an unfinished syscall, and is held back until the
+    /// matching resumed line is stitched.
+    Unfinished,
+
+    /// The line resumed an unfinished syscall. `line` is the full syscall
+    /// line and `timestamp` is the time the syscall started.
+    Resumed {
+        line: String,
+        timestamp: jiff::Timestamp,
+    },
+}
+
+#[derive(Debug)]
+struct UnfinishedLine {
+    name: String,
+    head: String,
+
+    /// The index in `head` where the syscall name starts.
+    call_start: usize,
+    timestamp: jiff::Timestamp,
+
+    /// Whether the syscall will be resumed under a different pid.
+    superseded: bool,
+}
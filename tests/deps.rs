@@ -0,0 +1,150 @@
+use std::collections::BTreeSet;
+
+use systrument::{
+    Pid,
+    deps::{DepsFormat, DepsOutput, DepsOutputOptions, ProcessDeps},
+    files::ProcessFiles,
+    strace::analyzer::Analyzer,
+};
+
+fn write_deps(lines: &str, options: DepsOutputOptions) -> String {
+    let mut analyzer = Analyzer::default();
+    let mut output = vec![];
+    let mut deps_output = DepsOutput::new(&mut output, options);
+
+    for line in lines.lines().map(|line| line.trim()) {
+        if line.is_empty() {
+            continue;
+        }
+
+        let strace = systrument::strace::parser::parse_line(line)
+            .map_err(|err| miette::Report::new(err).with_source_code(line.to_string()))
+            .unwrap();
+        let event = analyzer
+            .analyze(strace)
+            .map_err(|err| miette::Report::new(err).with_source_code(line.to_string()))
+            .unwrap();
+        deps_output.output_event(event);
+    }
+
+    deps_output.finish().unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+fn depfile(subtree: Option<Pid>) -> String {
+    write_deps(
+        MAKE_LINES,
+        DepsOutputOptions {
+            format: DepsFormat::Depfile,
+            subtree,
+            include_system_files: false,
+        },
+    )
+}
+
+fn paths(paths: &[&str]) -> BTreeSet<bstr::BString> {
+    paths
+        .iter()
+        .map(|path| bstr::BString::from(*path))
+        .collect()
+}
+
+const MAKE_LINES: &str = r#"
+    100 1757048541.000000 execve("/usr/bin/make", ["make"], []) = 0 <0.000100>
+    100 1757048541.000100 chdir("/src") = 0 <0.000010>
+    100 1757048541.000200 openat(AT_FDCWD, "Makefile", O_RDONLY|O_CLOEXEC) = 3 <0.000010>
+    100 1757048541.000300 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+    101 1757048541.000400 execve("/usr/bin/cc", ["cc", "-c", "main.c"], []) = 0 <0.000100>
+    101 1757048541.000500 openat(AT_FDCWD, "main.c", O_RDONLY) = 3 <0.000010>
+    101 1757048541.000600 openat(AT_FDCWD, "/dev/null", O_RDWR) = 4 <0.000010>
+    101 1757048541.000700 openat(AT_FDCWD, "main.o", O_WRONLY|O_CREAT|O_TRUNC, 0666) = 5 <0.000010>
+    101 1757048541.000800 +++ exited with 0 +++
+    100 1757048541.000900 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 102 <0.000100>
+    102 1757048541.001000 execve("/usr/bin/ld", ["ld", "-o", "my app$#", "main.o"], []) = 0 <0.000100>
+    102 1757048541.001100 openat(AT_FDCWD, "main.o", O_RDONLY) = 3 <0.000010>
+    102 1757048541.001200 openat(AT_FDCWD, "my app$#", O_WRONLY|O_CREAT|O_TRUNC, 0777) = 4 <0.000010>
+    102 1757048541.001300 +++ exited with 0 +++
+"#;
+
+#[test]
+fn test_process_deps() {
+    let process = ProcessFiles {
+        pid: 100,
+        parent_index: None,
+        command: Some("/usr/bin/cc".into()),
+        args: None,
+        read: paths(&["/src/main.c", "/tmp/cc1.s", "/proc/self/maps"]),
+        written: paths(&["/src/main.o", "/tmp/cc1.s"]),
+        created: paths(&["/src/main.o", "/tmp/cc1.s"]),
+        deleted: paths(&["/tmp/cc1.s"]),
+        not_found: paths(&["/src/config.h"]),
+    };
+
+    // Temporary files that were created and deleted aren't inputs or
+    // outputs
+    let deps = ProcessDeps::from_processes([&process], false);
+    assert_eq!(deps.inputs, paths(&["/src/main.c"]));
+    assert_eq!(deps.outputs, paths(&["/src/main.o"]));
+
+    let deps = ProcessDeps::from_processes([&process], true);
+    assert_eq!(deps.inputs, paths(&["/proc/self/maps", "/src/main.c"]));
+    assert_eq!(deps.outputs, paths(&["/src/main.o"]));
+}
+
+#[test]
+fn test_deps_depfile() {
+    // Processes without outputs (make) don't get a rule, and paths are
+    // escaped for Make
+    assert_eq!(
+        depfile(None),
+        "/src/main.o: \\\n  /src/main.c\n/src/my\\ app$$\\#: \\\n  /src/main.o\n"
+    );
+}
+
+#[test]
+fn test_deps_depfile_subtree() {
+    // Files created within the subtree aren't inputs
+    assert_eq!(
+        depfile(Some(100)),
+        "/src/main.o /src/my\\ app$$\\#: \\\n  /src/Makefile \\\n  /src/main.c\n"
+    );
+
+    // Siblings aren't included in the subtree
+    assert_eq!(depfile(Some(101)), "/src/main.o: \\\n  /src/main.c\n");
+    assert_eq!(
+        depfile(Some(102)),
+        "/src/my\\ app$$\\#: \\\n  /src/main.o\n"
+    );
+}
+
+#[test]
+fn test_deps_dot() {
+    let dot = write_deps(
+        MAKE_LINES,
+        DepsOutputOptions {
+            format: DepsFormat::Dot,
+            subtree: None,
+            include_system_files: false,
+        },
+    );
+    assert_eq!(
+        dot,
+        r#"digraph build {
+  p0 [shape=box, label="make (100)"];
+  f0 -> p0;
+  p1 [shape=box, label="cc (101)"];
+  p0 -> p1 [style=dashed];
+  f1 -> p1;
+  p1 -> f2;
+  p2 [shape=box, label="ld (102)"];
+  p0 -> p2 [style=dashed];
+  f2 -> p2;
+  p2 -> f3;
+  f0 [shape=note, label="/src/Makefile"];
+  f1 [shape=note, label="/src/main.c"];
+  f2 [shape=note, label="/src/main.o"];
+  f3 [shape=note, label="/src/my app$#"];
+}
+"#
+    );
+}
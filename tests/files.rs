@@ -0,0 +1,110 @@
+use std::collections::BTreeSet;
+
+use systrument::{
+    files::{FilesCollector, FilesFormat, FilesOutput, FilesOutputOptions},
+    strace::analyzer::Analyzer,
+};
+
+fn collect_files<'a>(
+    analyzer: &mut Analyzer,
+    lines: &'a str,
+    mut add_event: impl FnMut(systrument::event::Event<'a>),
+) {
+    for line in lines.lines().map(|line| line.trim()) {
+        if line.is_empty() {
+            continue;
+        }
+
+        let strace = systrument::strace::parser::parse_line(line)
+            .map_err(|err| miette::Report::new(err).with_source_code(line.to_string()))
+            .unwrap();
+        let event = analyzer
+            .analyze(strace)
+            .map_err(|err| miette::Report::new(err).with_source_code(line.to_string()))
+            .unwrap();
+        add_event(event);
+    }
+}
+
+fn paths(paths: &[&str]) -> BTreeSet<bstr::BString> {
+    paths
+        .iter()
+        .map(|path| bstr::BString::from(*path))
+        .collect()
+}
+
+const MAKE_LINES: &str = r#"
+    100 1757048541.000000 execve("/usr/bin/make", ["make"], []) = 0 <0.000100>
+    100 1757048541.000100 chdir("/src") = 0 <0.000010>
+    100 1757048541.000200 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+    101 1757048541.000300 openat(AT_FDCWD, "Makefile", O_RDONLY|O_CLOEXEC) = 3 <0.000010>
+    101 1757048541.000400 execve("/usr/bin/cc", ["cc", "-DNAME=\"a\\b\"", "main.c"], []) = 0 <0.000100>
+    101 1757048541.000500 openat(AT_FDCWD, "main.c", O_RDONLY) = 3 <0.000010>
+    101 1757048541.000600 openat(AT_FDCWD, "config.h", O_RDONLY) = -1 ENOENT (No such file or directory) <0.000010>
+    101 1757048541.000700 stat("main.c/config.h", 0x7ffd00000000) = -1 ENOTDIR (Not a directory) <0.000010>
+    101 1757048541.000800 openat(AT_FDCWD, "/etc/shadow", O_RDONLY) = -1 EACCES (Permission denied) <0.000010>
+    101 1757048541.000900 openat(AT_FDCWD, "out\tfile", O_WRONLY|O_CREAT|O_TRUNC, 0666) = 4 <0.000010>
+    101 1757048541.001000 +++ exited with 0 +++
+    100 1757048541.001100 unlink("main.o") = 0 <0.000010>
+"#;
+
+#[test]
+fn test_files_collector() {
+    let mut analyzer = Analyzer::default();
+    let mut collector = FilesCollector::default();
+    collect_files(&mut analyzer, MAKE_LINES, |event| {
+        collector.add_event(event)
+    });
+
+    let processes = collector.into_processes();
+    assert_eq!(processes.len(), 2);
+
+    // Accesses from the forked child before it exec'd belong to its owner
+    let make = &processes[0];
+    assert_eq!(make.pid, 100);
+    assert_eq!(make.parent_index, None);
+    assert_eq!(make.read, paths(&["/src/Makefile"]));
+    assert_eq!(make.deleted, paths(&["/src/main.o"]));
+    assert!(make.not_found.is_empty());
+
+    let cc = &processes[1];
+    assert_eq!(cc.pid, 101);
+    assert_eq!(cc.parent_index, Some(0));
+    assert_eq!(cc.command, Some("/usr/bin/cc".into()));
+    assert_eq!(cc.read, paths(&["/src/main.c"]));
+    assert_eq!(cc.written, paths(&["/src/out\tfile"]));
+    assert_eq!(cc.created, paths(&["/src/out\tfile"]));
+    assert!(cc.deleted.is_empty());
+
+    // Failed accesses other than missing files aren't included
+    assert_eq!(
+        cc.not_found,
+        paths(&["/src/config.h", "/src/main.c/config.h"])
+    );
+}
+
+#[test]
+fn test_files_output_json() {
+    let mut analyzer = Analyzer::default();
+    let mut json = vec![];
+    let mut output = FilesOutput::new(
+        &mut json,
+        FilesOutputOptions {
+            format: FilesFormat::Json,
+        },
+    );
+    collect_files(&mut analyzer, MAKE_LINES, |event| {
+        output.output_event(event)
+    });
+    output.finish().unwrap();
+
+    let json = String::from_utf8(json).unwrap();
+    assert_eq!(
+        json,
+        r#"[
+  {"pid": 100, "parent_pid": null, "command": "/usr/bin/make", "args": ["make"], "read": ["/src/Makefile"], "written": [], "created": [], "deleted": ["/src/main.o"], "not_found": []},
+  {"pid": 101, "parent_pid": 100, "command": "/usr/bin/cc", "args": ["cc", "-DNAME=\"a\\b\"", "main.c"], "read": ["/src/main.c"], "written": ["/src/out\tfile"], "created": ["/src/out\tfile"], "deleted": [], "not_found": ["/src/config.h", "/src/main.c/config.h"]}
+]
+"#
+    );
+}
@@ -0,0 +1,90 @@
+use perfetto_protos::{
+    trace::Trace,
+    trace_packet::trace_packet,
+    track_descriptor::track_descriptor,
+    track_event::{TrackEvent, track_event},
+};
+use protobuf::Message as _;
+use systrument::{
+    perfetto::{PerfettoOutput, PerfettoOutputOptions},
+    strace::analyzer::Analyzer,
+};
+
+fn write_trace(lines: &str, options: PerfettoOutputOptions) -> Trace {
+    let mut analyzer = Analyzer::default();
+    let mut output = PerfettoOutput::new(vec![], options);
+
+    for line in lines.lines().map(|line| line.trim()) {
+        if line.is_empty() {
+            continue;
+        }
+
+        let strace = systrument::strace::parser::parse_line(line)
+            .map_err(|err| miette::Report::new(err).with_source_code(line.to_string()))
+            .unwrap();
+        let event = analyzer
+            .analyze(strace)
+            .map_err(|err| miette::Report::new(err).with_source_code(line.to_string()))
+            .unwrap();
+        output.output_event(event).unwrap();
+    }
+
+    let output = output.finish().unwrap();
+    Trace::parse_from_bytes(&output).unwrap()
+}
+
+fn track_events(trace: &Trace) -> impl Iterator<Item = &TrackEvent> {
+    trace.packet.iter().filter_map(|packet| match &packet.data {
+        Some(trace_packet::Data::TrackEvent(track_event)) => Some(track_event),
+        _ => None,
+    })
+}
+
+fn counter_values(trace: &Trace, track_name: &str) -> Vec<i64> {
+    let track_uuid = trace
+        .packet
+        .iter()
+        .find_map(|packet| match &packet.data {
+            Some(trace_packet::Data::TrackDescriptor(track_descriptor))
+                if track_descriptor.static_or_dynamic_name
+                    == Some(track_descriptor::Static_or_dynamic_name::Name(
+                        track_name.to_string(),
+                    )) =>
+            {
+                track_descriptor.uuid
+            }
+            _ => None,
+        })
+        .expect("counter track not found");
+
+    track_events(trace)
+        .filter(|track_event| track_event.track_uuid == Some(track_uuid))
+        .filter_map(|track_event| match track_event.counter_value_field {
+            Some(track_event::Counter_value_field::CounterValue(value)) => Some(value),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_perfetto_live_processes() {
+    let trace = write_trace(
+        r#"
+            100 1757048541.000000 execve("/bin/sh", ["sh"], []) = 0 <0.000100>
+            100 1757048541.000100 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+            101 1757048541.000200 execve("/bin/true", ["true"], []) = 0 <0.000100>
+            101 1757048541.000300 exit_group(0) = ?
+            101 1757048541.000400 +++ exited with 0 +++
+            100 1757048541.000500 exit_group(0) = ?
+            100 1757048541.000600 +++ exited with 0 +++
+        "#,
+        PerfettoOutputOptions {
+            counters: true,
+            ..Default::default()
+        },
+    );
+
+    // The `+++ exited` lines after `exit_group` don't count the processes
+    // as live again
+    assert_eq!(counter_values(&trace, "Live processes"), [1, 2, 1, 0]);
+}
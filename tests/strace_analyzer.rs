@@ -0,0 +1,693 @@
+use systrument::{
+    event::{Event, EventKind, FileAccessMode, ProcessStoppedReason},
+    strace::{
+        analyzer::{Analyzer, FileDescriptor, FileDescriptorKind},
+        parser::LineParser,
+        stitcher::{Stitched, Stitcher},
+    },
+};
+
+fn analyze_lines<'a>(analyzer: &mut Analyzer, lines: &'a str) -> Vec<Event<'a>> {
+    lines
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let strace = systrument::strace::parser::parse_line(line)
+                .map_err(|err| miette::Report::new(err).with_source_code(line.to_string()))
+                .unwrap();
+            analyzer
+                .analyze(strace)
+                .map_err(|err| miette::Report::new(err).with_source_code(line.to_string()))
+                .unwrap()
+        })
+        .collect()
+}
+
+/// Join unfinished syscalls with their resumed halves. Stitched lines are
+/// kept where they were resumed. Returns each line along with its timestamp.
+fn stitch_lines(lines: &str) -> Vec<(String, jiff::Timestamp)> {
+    let mut stitcher = Stitcher::default();
+    let mut stitched_lines = vec![];
+    for line in lines.lines().map(|line| line.trim()) {
+        if line.is_empty() {
+            continue;
+        }
+
+        let strace = systrument::strace::parser::parse_line(line).unwrap();
+        match stitcher.stitch(&strace) {
+            Stitched::Complete => stitched_lines.push((line.to_string(), strace.timestamp)),
+            Stitched::Unfinished => {}
+            Stitched::Resumed { line, timestamp } => stitched_lines.push((line, timestamp)),
+        }
+    }
+
+    stitched_lines
+}
+
+/// Order stitched lines by when they should be analyzed, the same way the
+/// CLI does.
+fn sort_stitched_lines(lines: &mut [(String, jiff::Timestamp)]) {
+    let parser = LineParser::default();
+    lines.sort_by_key(|(line, timestamp)| {
+        parser.parse_line_at(line, *timestamp).unwrap().analyze_at()
+    });
+}
+
+/// Analyze stitched lines in order, returning the kind of each event.
+fn analyze_stitched_lines(
+    analyzer: &mut Analyzer,
+    lines: &[(String, jiff::Timestamp)],
+) -> Vec<EventKind> {
+    let parser = LineParser::default();
+    lines
+        .iter()
+        .map(|(line, timestamp)| {
+            let strace = parser.parse_line_at(line, *timestamp).unwrap();
+            analyzer.analyze(strace).unwrap().kind
+        })
+        .collect()
+}
+
+fn file(path: &str, cloexec: bool) -> FileDescriptor {
+    FileDescriptor {
+        kind: FileDescriptorKind::File,
+        path: Some(path.into()),
+        cloexec,
+    }
+}
+
+#[test]
+fn test_analyzer_fd_table() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/bin/sh", ["sh"], []) = 0 <0.000100>
+            100 1757048541.000100 openat(AT_FDCWD, "/etc/passwd", O_RDONLY|O_CLOEXEC) = 3 <0.000010>
+            100 1757048541.000200 open("/tmp/out", O_WRONLY|O_CREAT|O_TRUNC, 0644) = 4 <0.000010>
+            100 1757048541.000300 pipe2([5, 6], O_CLOEXEC) = 0 <0.000010>
+            100 1757048541.000400 dup2(4, 1) = 1 <0.000010>
+            100 1757048541.000500 fcntl(3, F_DUPFD_CLOEXEC, 10) = 10 <0.000010>
+            100 1757048541.000600 openat(AT_FDCWD, "/nope", O_RDONLY) = -1 ENOENT (No such file or directory) <0.000010>
+            100 1757048541.000700 close(4) = 0 <0.000010>
+        "#,
+    );
+
+    let open_fds: Vec<_> = events.iter().map(|event| event.open_fds).collect();
+    assert_eq!(open_fds, [0, 1, 2, 4, 5, 6, 6, 5].map(Some));
+
+    assert_eq!(
+        analyzer.file_descriptor(100, 3),
+        Some(&file("/etc/passwd", true))
+    );
+    assert_eq!(analyzer.file_descriptor(100, 4), None);
+    assert_eq!(
+        analyzer.file_descriptor(100, 1),
+        Some(&file("/tmp/out", false))
+    );
+    assert_eq!(
+        analyzer.file_descriptor(100, 5).map(|fd| fd.kind),
+        Some(FileDescriptorKind::Pipe)
+    );
+    assert_eq!(
+        analyzer.file_descriptor(100, 10),
+        Some(&file("/etc/passwd", true))
+    );
+
+    analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.001000 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+            100 1757048541.001100 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0, stack=0x7f0000000000, stack_size=0x1000}, 88) = 102 <0.000100>
+            102 1757048541.001200 openat(AT_FDCWD, "/etc/hosts", O_RDONLY) = 7 <0.000010>
+            101 1757048541.001300 close(1) = 0 <0.000010>
+            101 1757048541.001400 execve("/bin/cat", ["cat"], []) = 0 <0.000100>
+        "#,
+    );
+
+    // Threads share the fd table, forked processes get a copy
+    assert_eq!(
+        analyzer.file_descriptor(100, 7),
+        Some(&file("/etc/hosts", false))
+    );
+    assert_eq!(analyzer.file_descriptor(101, 7), None);
+    assert_eq!(
+        analyzer.file_descriptor(100, 1),
+        Some(&file("/tmp/out", false))
+    );
+    assert_eq!(analyzer.file_descriptor(101, 1), None);
+
+    // Close-on-exec fds are closed after exec
+    assert_eq!(analyzer.file_descriptor(101, 3), None);
+    assert_eq!(analyzer.file_descriptor(101, 5), None);
+    assert_eq!(
+        analyzer.file_descriptor(100, 3),
+        Some(&file("/etc/passwd", true))
+    );
+}
+
+#[test]
+fn test_analyzer_resolve_paths() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/bin/sh", ["sh"], []) = 0 <0.000100>
+            100 1757048541.000100 openat(AT_FDCWD</home/user>, "src/main.c", O_RDONLY) = 3</home/user/src/main.c> <0.000010>
+            100 1757048541.000200 chdir("build") = 0 <0.000010>
+            100 1757048541.000300 stat("./main.o", 0x7ffd00000000) = -1 ENOENT (No such file or directory) <0.000010>
+            100 1757048541.000400 openat(AT_FDCWD, "/usr/include/stdio.h", O_RDONLY) = 4 <0.000010>
+            100 1757048541.000500 openat(AT_FDCWD, "../include", O_RDONLY|O_DIRECTORY) = 5 <0.000010>
+            100 1757048541.000600 newfstatat(5, "config.h", 0x7ffd00000000, 0) = 0 <0.000010>
+            100 1757048541.000700 renameat2(AT_FDCWD, "main.o.tmp", 5, "main.o", 0) = 0 <0.000010>
+            100 1757048541.000800 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+            101 1757048541.000900 fchdir(5) = 0 <0.000010>
+            101 1757048541.001000 execveat(AT_FDCWD, "tool", ["tool"], [], 0) = 0 <0.000100>
+        "#,
+    );
+
+    let paths = events
+        .iter()
+        .map(|event| {
+            event
+                .paths
+                .iter()
+                .map(|path| path.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            vec!["/bin/sh"],
+            vec!["/home/user/src/main.c"],
+            vec!["/home/user/build"],
+            vec!["/home/user/build/main.o"],
+            vec!["/usr/include/stdio.h"],
+            vec!["/home/user/build/../include"],
+            vec!["/home/user/build/../include/config.h"],
+            vec![
+                "/home/user/build/main.o.tmp",
+                "/home/user/build/../include/main.o"
+            ],
+            vec![],
+            vec![],
+            vec!["/home/user/build/../include/tool"],
+        ],
+    );
+
+    assert_eq!(analyzer.cwd(100), Some("/home/user/build".into()));
+
+    let EventKind::ExecProcess(exec) = &events[10].kind else {
+        panic!("expected exec event, got {:?}", events[10].kind);
+    };
+    assert_eq!(
+        exec.exec
+            .command
+            .as_ref()
+            .map(|command| command.to_string()),
+        Some("/home/user/build/../include/tool".to_string())
+    );
+}
+
+#[test]
+fn test_analyzer_file_access() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/usr/bin/cc", ["cc"], []) = 0 <0.000100>
+            100 1757048541.000100 chdir("/src") = 0 <0.000010>
+            100 1757048541.000200 openat(AT_FDCWD, "main.c", O_RDONLY|O_CLOEXEC) = 3 <0.000010>
+            100 1757048541.000300 openat(AT_FDCWD, "main.o", O_WRONLY|O_CREAT|O_TRUNC, 0666) = 4 <0.000010>
+            100 1757048541.000400 access("config.h", R_OK) = -1 ENOENT (No such file or directory) <0.000010>
+            100 1757048541.000500 rename("main.o", "out.o") = 0 <0.000010>
+            100 1757048541.000600 unlinkat(AT_FDCWD, "tmp", AT_REMOVEDIR) = 0 <0.000010>
+            100 1757048541.000700 close(3) = 0 <0.000010>
+            100 1757048541.000800 openat(AT_FDCWD, "cache.db", O_RDWR|O_CLOEXEC) = 3 <0.000010>
+            100 1757048541.000900 openat(AT_FDCWD, "build.lock", O_WRONLY|O_CREAT|O_EXCL, 0644) = 5 <0.000010>
+            100 1757048541.001000 rename(0x7ffd00000000, "new.o") = -1 EFAULT (Bad address) <0.000010>
+        "#,
+    );
+
+    let file_accesses = events
+        .iter()
+        .filter_map(|event| {
+            let EventKind::FileAccess(file_access) = &event.kind else {
+                return None;
+            };
+
+            let accesses = file_access
+                .accesses
+                .iter()
+                .map(|access| (access.path.to_string(), access.mode))
+                .collect::<Vec<_>>();
+            Some((accesses, file_access.flags.clone(), file_access.success))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        file_accesses,
+        [
+            (
+                vec![("/src/main.c".to_string(), FileAccessMode::Read)],
+                Some("O_RDONLY|O_CLOEXEC".to_string()),
+                true
+            ),
+            (
+                vec![
+                    ("/src/main.o".to_string(), FileAccessMode::Write),
+                    ("/src/main.o".to_string(), FileAccessMode::Create),
+                ],
+                Some("O_WRONLY|O_CREAT|O_TRUNC".to_string()),
+                true
+            ),
+            (
+                vec![("/src/config.h".to_string(), FileAccessMode::Metadata)],
+                None,
+                false
+            ),
+            (
+                vec![
+                    ("/src/main.o".to_string(), FileAccessMode::Delete),
+                    ("/src/out.o".to_string(), FileAccessMode::Create),
+                ],
+                None,
+                true
+            ),
+            (
+                vec![("/src/tmp".to_string(), FileAccessMode::Delete)],
+                None,
+                true
+            ),
+            (
+                vec![
+                    ("/src/cache.db".to_string(), FileAccessMode::Read),
+                    ("/src/cache.db".to_string(), FileAccessMode::Write),
+                ],
+                Some("O_RDWR|O_CLOEXEC".to_string()),
+                true
+            ),
+            (
+                vec![("/src/build.lock".to_string(), FileAccessMode::Create)],
+                Some("O_WRONLY|O_CREAT|O_EXCL".to_string()),
+                true
+            ),
+            // Path arguments that aren't strings don't shift the others
+            (
+                vec![("/src/new.o".to_string(), FileAccessMode::Create)],
+                None,
+                false
+            ),
+        ],
+    );
+}
+
+#[test]
+fn test_analyzer_fd_paths() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/bin/cat", ["cat"], []) = 0 <0.000100>
+            100 1757048541.000100 openat(AT_FDCWD, "/etc/hosts", O_RDONLY) = 3 <0.000010>
+            100 1757048541.000200 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0, stack=0x7f0000000000, stack_size=0x1000}, 88) = 101 <0.000100>
+            101 1757048541.000300 read(3, "127.0.0.1 localhost\n", 4096) = 20 <0.000010>
+            101 1757048541.000400 +++ exited with 0 +++
+            100 1757048541.000500 write(1</dev/pts/0>, "127.0.0.1 localhost\n", 20) = 20 <0.000010>
+            100 1757048541.000600 read(3, "", 4096) = 0 <0.000010>
+        "#,
+    );
+
+    let fd_paths: Vec<_> = events
+        .iter()
+        .map(|event| event.fd_path.as_ref().map(|path| path.to_string()))
+        .collect();
+    assert_eq!(
+        fd_paths,
+        [
+            None,
+            None,
+            None,
+            Some("/etc/hosts".to_string()),
+            None,
+            Some("/dev/pts/0".to_string()),
+            Some("/etc/hosts".to_string()),
+        ]
+    );
+
+    // The fd table is kept until every thread sharing it has stopped
+    assert_eq!(
+        analyzer.file_descriptor(100, 3),
+        Some(&file("/etc/hosts", false))
+    );
+    analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000700 exit_group(0) = ?
+            100 1757048541.000800 +++ exited with 0 +++
+        "#,
+    );
+    assert_eq!(analyzer.file_descriptor(100, 3), None);
+}
+
+#[test]
+fn test_analyzer_unfinished_clone() {
+    let lines = stitch_lines(
+        r#"
+            100 1757048541.000000 execve("/bin/cat", ["cat"], []) = 0 <0.000100>
+            100 1757048541.000100 openat(AT_FDCWD, "/etc/hosts", O_RDONLY) = 3 <0.000010>
+            100 1757048541.000200 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0, stack=0x7f0000000000, stack_size=0x1000}, 88 <unfinished ...>
+            101 1757048541.000300 read(3, "127.0.0.1 localhost\n", 4096) = 20 <0.000010>
+            100 1757048541.000400 <... clone3 resumed>) = 101 <0.000200>
+            100 1757048541.000500 chdir("/tmp") = 0 <0.000010>
+        "#,
+    );
+
+    // The child's first line is analyzed before the `clone` that created it
+    let mut analyzer = Analyzer::default();
+    let kinds = analyze_stitched_lines(&mut analyzer, &lines);
+    let EventKind::ForkProcess(fork) = &kinds[3] else {
+        panic!("expected fork event, got {:?}", kinds[3]);
+    };
+    assert_eq!(fork.child_pid, 101);
+    assert_eq!(fork.child_tgid, 100);
+    assert!(fork.thread);
+
+    // The child still joins the thread group, and shares the fd table and cwd
+    assert_eq!(analyzer.tgid(101), Some(100));
+    assert_eq!(
+        analyzer.file_descriptor(101, 3),
+        Some(&file("/etc/hosts", false))
+    );
+    assert_eq!(analyzer.cwd(101), Some(bstr::BStr::new("/tmp")));
+}
+
+#[test]
+fn test_analyzer_threads() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/usr/bin/ld", ["ld"], []) = 0 <0.000100>
+            100 1757048541.000100 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0, stack=0x7f0000000000, stack_size=0x1000}, 88) = 101 <0.000100>
+            101 1757048541.000200 clone(child_stack=0x7f0000100000, flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM) = 102 <0.000100>
+            101 1757048541.000300 vfork() = 103 <0.000100>
+            103 1757048541.000400 execve("/bin/true", ["true"], []) = 0 <0.000100>
+            103 1757048541.000500 +++ exited with 0 +++
+            102 1757048541.000600 +++ exited with 0 +++
+            100 1757048541.000700 +++ exited with 0 +++
+        "#,
+    );
+
+    let forks = events
+        .iter()
+        .filter_map(|event| {
+            let EventKind::ForkProcess(fork) = &event.kind else {
+                return None;
+            };
+            Some((
+                fork.child_pid,
+                fork.child_tgid,
+                fork.thread,
+                fork.shared_memory,
+                fork.child_owner_pid,
+            ))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        forks,
+        [
+            (101, 100, true, true, Some(100)),
+            (102, 100, true, true, Some(100)),
+            (103, 103, false, true, Some(100)),
+        ],
+    );
+
+    assert_eq!(analyzer.tgid(102), Some(100));
+    assert_eq!(events[3].tgid, 100);
+    assert_eq!(events[4].parent_pid, Some(100));
+
+    let stops = events
+        .iter()
+        .filter_map(|event| {
+            let EventKind::StopProcess(stop) = &event.kind else {
+                return None;
+            };
+            Some((event.pid, stop.thread))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(stops, [(103, false), (102, true), (100, false)]);
+}
+
+#[test]
+fn test_analyzer_exec_from_thread() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/usr/bin/make", ["make"], []) = 0 <0.000100>
+            100 1757048541.000100 chdir("/src") = 0 <0.000010>
+            100 1757048541.000200 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0, stack=0x7f0000000000, stack_size=0x1000}, 88) = 101 <0.000100>
+            100 1757048541.000300 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0, stack=0x7f0000000000, stack_size=0x1000}, 88) = 102 <0.000100>
+            100 1757048541.000400 +++ superseded by execve in pid 101 +++
+            100 1757048541.000500 execve("/bin/sh", ["sh"], []) = 0 <0.000100>
+            100 1757048541.000600 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0, stack=0x7f0000000000, stack_size=0x1000}, 88) = 103 <0.000100>
+            103 1757048541.000700 exit_group(2) = ?
+            103 1757048541.000800 +++ exited with 2 +++
+            100 1757048541.000900 +++ exited with 2 +++
+        "#,
+    );
+
+    // The thread's state moves to the leader's pid, and the other thread
+    // gets killed
+    assert!(matches!(events[4].kind, EventKind::Log));
+    assert_eq!(analyzer.tgid(101), None);
+    assert_eq!(analyzer.cwd(100), Some("/src".into()));
+
+    let EventKind::ExecProcess(exec) = &events[5].kind else {
+        panic!("expected exec event, got {:?}", events[5].kind);
+    };
+    assert!(exec.re_exec);
+
+    // `exit_group` stops the whole thread group at once
+    let EventKind::StopProcess(stop) = &events[7].kind else {
+        panic!("expected stop event, got {:?}", events[7].kind);
+    };
+    assert!(!stop.thread);
+    assert!(stop.did_exec);
+    assert!(matches!(
+        stop.stopped,
+        ProcessStoppedReason::Exited { code: Some(2) }
+    ));
+    assert_eq!(events[7].tgid, 100);
+
+    assert!(matches!(events[8].kind, EventKind::Log));
+    assert!(matches!(events[9].kind, EventKind::Log));
+}
+
+#[test]
+fn test_analyzer_signals() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/usr/bin/timeout", ["timeout", "1", "sleep", "5"], []) = 0 <0.000100>
+            100 1757048541.000100 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+            101 1757048541.000200 execve("/usr/bin/sleep", ["sleep", "5"], []) = 0 <0.000100>
+            100 1757048542.000000 kill(101, SIGTERM) = 0 <0.000010>
+            101 1757048542.000100 --- SIGTERM {si_signo=SIGTERM, si_code=SI_USER, si_pid=100, si_uid=1000} ---
+            101 1757048542.000200 +++ killed by SIGTERM +++
+            100 1757048542.000300 --- SIGCHLD {si_signo=SIGCHLD, si_code=CLD_KILLED, si_pid=101, si_uid=1000, si_status=SIGTERM, si_utime=0, si_stime=0} ---
+        "#,
+    );
+
+    let EventKind::Signal(sigterm) = &events[4].kind else {
+        panic!("expected signal event, got {:?}", events[4].kind);
+    };
+    assert_eq!(sigterm.signal, "SIGTERM");
+    assert_eq!(sigterm.code.as_deref(), Some("SI_USER"));
+    assert_eq!(sigterm.sender_pid, Some(100));
+    assert_eq!(sigterm.status, None);
+    let sent_by = sigterm.sent_by.as_ref().unwrap();
+    assert_eq!(sent_by.pid, 100);
+    assert_eq!(sent_by.syscall, "kill");
+    assert_eq!(sent_by.timestamp, jiff::Timestamp::constant(1757048542, 0));
+
+    let EventKind::Signal(sigchld) = &events[6].kind else {
+        panic!("expected signal event, got {:?}", events[6].kind);
+    };
+    assert_eq!(sigchld.signal, "SIGCHLD");
+    assert_eq!(sigchld.code.as_deref(), Some("CLD_KILLED"));
+    assert_eq!(sigchld.sender_pid, Some(101));
+    assert_eq!(sigchld.status.as_deref(), Some("SIGTERM"));
+    assert!(sigchld.sent_by.is_none());
+}
+
+#[test]
+fn test_analyzer_signal_to_stopped_process() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/bin/sh", ["sh"], []) = 0 <0.000100>
+            100 1757048541.000100 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+            100 1757048541.000200 kill(101, SIGUSR1) = 0 <0.000010>
+            101 1757048541.000300 +++ exited with 0 +++
+            100 1757048541.000400 wait4(-1, [{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL) = 101 <0.000010>
+            100 1757048541.000500 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+            101 1757048541.000600 --- SIGUSR1 {si_signo=SIGUSR1, si_code=SI_USER, si_pid=1, si_uid=0} ---
+        "#,
+    );
+
+    // The signal sent to the first process with the pid isn't matched to
+    // the signal delivered to the second one
+    let EventKind::Signal(sigusr1) = &events[6].kind else {
+        panic!("expected signal event, got {:?}", events[6].kind);
+    };
+    assert_eq!(sigusr1.signal, "SIGUSR1");
+    assert!(sigusr1.sent_by.is_none());
+}
+
+#[test]
+fn test_analyzer_suspend_continue() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/usr/bin/sleep", ["sleep", "5"], []) = 0 <0.000100>
+            100 1757048541.500000 --- SIGTSTP {si_signo=SIGTSTP, si_code=SI_KERNEL} ---
+            100 1757048541.500100 --- stopped by SIGTSTP ---
+            100 1757048541.600000 --- stopped by SIGTSTP ---
+            100 1757048543.500100 --- SIGCONT {si_signo=SIGCONT, si_code=SI_USER, si_pid=99, si_uid=1000} ---
+            100 1757048543.600000 --- SIGCONT {si_signo=SIGCONT, si_code=SI_USER, si_pid=99, si_uid=1000} ---
+        "#,
+    );
+
+    let EventKind::SuspendProcess(suspend) = &events[2].kind else {
+        panic!("expected suspend event, got {:?}", events[2].kind);
+    };
+    assert_eq!(suspend.signal, "SIGTSTP");
+
+    // Repeated group-stops don't suspend the process again
+    assert!(matches!(events[3].kind, EventKind::Log));
+
+    let EventKind::ContinueProcess(continued) = &events[4].kind else {
+        panic!("expected continue event, got {:?}", events[4].kind);
+    };
+    assert_eq!(
+        continued.suspended_at,
+        jiff::Timestamp::constant(1757048541, 500100000)
+    );
+    assert_eq!(continued.signal.sender_pid, Some(99));
+
+    // `SIGCONT` is just a signal if the process isn't suspended
+    assert!(matches!(events[5].kind, EventKind::Signal(_)));
+}
+
+#[test]
+fn test_analyzer_reap() {
+    let mut analyzer = Analyzer::default();
+    let events = analyze_lines(
+        &mut analyzer,
+        r#"
+            100 1757048541.000000 execve("/bin/sh", ["sh"], []) = 0 <0.000100>
+            100 1757048541.000100 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+            101 1757048541.000200 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 102 <0.000100>
+            101 1757048541.000300 +++ exited with 0 +++
+            100 1757048541.000400 wait4(-1, [{WIFEXITED(s) && WEXITSTATUS(s) == 0}], WNOHANG, NULL) = 101 <0.000010>
+            100 1757048541.000500 wait4(-1, 0x7ffd00000000, WNOHANG, NULL) = 0 <0.000010>
+            102 1757048541.000600 --- stopped by SIGSTOP ---
+            100 1757048541.000700 wait4(-1, [{WIFSTOPPED(s) && WSTOPSIG(s) == SIGSTOP}], WUNTRACED, NULL) = 102 <0.000010>
+            102 1757048542.000000 +++ exited with 1 +++
+            100 1757048543.000000 waitid(P_ALL, 0, {si_signo=SIGCHLD, si_code=CLD_EXITED, si_pid=102, si_uid=1000, si_status=1, si_utime=0, si_stime=0}, WEXITED, NULL) = 0 <0.500000>
+            100 1757048544.000000 wait4(-1, [{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL) = 103 <0.000010>
+        "#,
+    );
+
+    let reaps = events
+        .iter()
+        .filter_map(|event| {
+            let EventKind::ReapProcess(reap) = &event.kind else {
+                return None;
+            };
+            Some(reap)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(reaps.len(), 2);
+
+    assert_eq!(reaps[0].child_pid, 101);
+    assert_eq!(
+        reaps[0].exited_at,
+        Some(jiff::Timestamp::constant(1757048541, 300000))
+    );
+    assert_eq!(
+        reaps[0].reaped_at,
+        jiff::Timestamp::constant(1757048541, 410000)
+    );
+    assert!(!reaps[0].orphan);
+
+    // The grandchild was reparented after its parent exited
+    assert_eq!(reaps[1].child_pid, 102);
+    assert_eq!(
+        reaps[1].exited_at,
+        Some(jiff::Timestamp::constant(1757048542, 0))
+    );
+    assert_eq!(
+        reaps[1].reaped_at,
+        jiff::Timestamp::constant(1757048543, 500000000)
+    );
+    assert_eq!(
+        reaps[1].wait_duration,
+        std::time::Duration::from_millis(500)
+    );
+    assert!(reaps[1].orphan);
+
+    // Children that were never traced aren't tracked when they're reaped
+    assert_eq!(analyzer.tgid(103), None);
+}
+
+#[test]
+fn test_analyzer_reap_blocking_wait() {
+    let mut lines = stitch_lines(
+        r#"
+            100 1757048541.000000 execve("/bin/sh", ["sh"], []) = 0 <0.000100>
+            100 1757048541.000100 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f0000000000) = 101 <0.000100>
+            100 1757048541.000200 wait4(-1,  <unfinished ...>
+            101 1757048541.000300 execve("/bin/true", ["true"], []) = 0 <0.000050>
+            101 1757048541.000400 exit_group(0) = ?
+            101 1757048541.000500 +++ exited with 0 +++
+            100 1757048541.000600 <... wait4 resumed>[{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL) = 101 <0.000400>
+        "#,
+    );
+    sort_stitched_lines(&mut lines);
+
+    let mut analyzer = Analyzer::default();
+    let reaps: Vec<_> = analyze_stitched_lines(&mut analyzer, &lines)
+        .into_iter()
+        .filter_map(|kind| {
+            let EventKind::ReapProcess(reap) = kind else {
+                return None;
+            };
+            Some(reap)
+        })
+        .collect();
+
+    // The wait started before the child exited, but it gets analyzed after
+    // the child's exit since that's when it returned
+    assert_eq!(reaps.len(), 1);
+    assert_eq!(reaps[0].child_pid, 101);
+    assert_eq!(
+        reaps[0].exited_at,
+        Some(jiff::Timestamp::constant(1757048541, 400000))
+    );
+    assert_eq!(
+        reaps[0].reaped_at,
+        jiff::Timestamp::constant(1757048541, 600000)
+    );
+    assert_eq!(
+        reaps[0].wait_duration,
+        std::time::Duration::from_micros(400)
+    );
+    assert!(!reaps[0].orphan);
+}
@@ -1,4 +1,8 @@
-use systrument::strace::{Event, SyscallEvent};
+use systrument::strace::{
+    Event, SyscallError, SyscallEvent,
+    parser::{LineParser, ParserOptions, TimestampFormat},
+    stitcher::{Stitched, Stitcher},
+};
 
 fn parse_strace_line(line: &'_ str) -> miette::Result<systrument::strace::Line<'_>> {
     systrument::strace::parser::parse_line(line)
@@ -189,3 +193,381 @@ fn test_strace_parse_line_examples() {
     );
     assert_eq!(syscall.result_string.value, "0");
 }
+
+#[test]
+fn test_strace_parse_line_unfinished_resumed() {
+    let strace =
+        parse_strace_line("2386276 1757048489.047729 wait4(-1,  <unfinished ...>").unwrap();
+    assert_eq!(strace.pid, 2386276);
+    assert_eq!(
+        strace.timestamp,
+        jiff::Timestamp::constant(1757048489, 47729000),
+    );
+    let Event::Unfinished(unfinished) = strace.event else {
+        panic!("expected unfinished event, got {:?}", strace.event);
+    };
+    assert_eq!(unfinished.name, "wait4");
+    assert_eq!(unfinished.args_string.value, "-1, ");
+
+    let strace = parse_strace_line(
+        "2386276 1757048490.393433 <... wait4 resumed>[{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL) = 2386283 <1.345704>",
+    )
+    .unwrap();
+    assert_eq!(strace.pid, 2386276);
+    assert_eq!(
+        strace.timestamp,
+        jiff::Timestamp::constant(1757048490, 393433000),
+    );
+    let Event::Resumed(resumed) = strace.event else {
+        panic!("expected resumed event, got {:?}", strace.event);
+    };
+    assert_eq!(resumed.name, "wait4");
+    assert_eq!(
+        resumed.args_string.value,
+        "[{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL"
+    );
+    assert_eq!(resumed.result_string.value, "2386283");
+    assert_eq!(resumed.duration, std::time::Duration::from_micros(1345704));
+
+    let strace = parse_strace_line(
+        "2386236 1757048490.393433 futex(0x7f299461a648, FUTEX_WAIT_PRIVATE, 1, NULL <unfinished ...>",
+    )
+    .unwrap();
+    let Event::Unfinished(unfinished) = strace.event else {
+        panic!("expected unfinished event, got {:?}", strace.event);
+    };
+    assert_eq!(unfinished.name, "futex");
+    assert_eq!(
+        unfinished.args_string.value,
+        "0x7f299461a648, FUTEX_WAIT_PRIVATE, 1, NULL"
+    );
+
+    let strace =
+        parse_strace_line("2386236 1757048491.393433 <... futex resumed>) = 0 <1.000000>").unwrap();
+    let Event::Resumed(resumed) = strace.event else {
+        panic!("expected resumed event, got {:?}", strace.event);
+    };
+    assert_eq!(resumed.name, "futex");
+    assert_eq!(resumed.args_string.value, "");
+    assert_eq!(resumed.result_string.value, "0");
+}
+
+#[test]
+fn test_strace_stitch_unfinished_resumed() {
+    let mut stitcher = Stitcher::default();
+
+    let unfinished =
+        parse_strace_line("2386276 1757048489.047729 wait4(-1,  <unfinished ...>").unwrap();
+    assert!(matches!(stitcher.stitch(&unfinished), Stitched::Unfinished));
+
+    let other =
+        parse_strace_line("2386283 1757048489.500000 brk(NULL)     = 0x555589aac000 <0.000007>")
+            .unwrap();
+    assert!(matches!(stitcher.stitch(&other), Stitched::Complete));
+
+    let resumed = parse_strace_line(
+        "2386276 1757048490.393433 <... wait4 resumed>[{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL) = 2386283 <1.345704>",
+    )
+    .unwrap();
+    let Stitched::Resumed { line, timestamp } = stitcher.stitch(&resumed) else {
+        panic!("expected resumed line");
+    };
+    assert_eq!(
+        line,
+        "2386276 1757048489.047729 wait4(-1, [{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL) = 2386283 <1.345704>"
+    );
+    assert_eq!(timestamp, jiff::Timestamp::constant(1757048489, 47729000));
+
+    let syscall = parse_strace_line_syscall(&line).unwrap();
+    assert_eq!(syscall.name, "wait4");
+    assert_eq!(
+        syscall.args_string.value,
+        "-1, [{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL"
+    );
+    assert_eq!(syscall.result_string.value, "2386283");
+    assert_eq!(syscall.duration, std::time::Duration::from_micros(1345704));
+
+    // A resumed line without an unfinished half is passed through as-is
+    assert!(matches!(stitcher.stitch(&resumed), Stitched::Complete));
+}
+
+#[test]
+fn test_strace_parse_line_superseded() {
+    let strace = parse_strace_line("100 1757048541.000000 +++ superseded by execve +++").unwrap();
+    assert_eq!(strace.pid, 100);
+    let Event::Superseded { thread_pid } = strace.event else {
+        panic!("expected superseded event, got {:?}", strace.event);
+    };
+    assert_eq!(thread_pid, None);
+
+    let strace =
+        parse_strace_line("100 1757048541.000000 +++ superseded by execve in pid 101 +++").unwrap();
+    assert_eq!(strace.pid, 100);
+    let Event::Superseded { thread_pid } = strace.event else {
+        panic!("expected superseded event, got {:?}", strace.event);
+    };
+    assert_eq!(thread_pid, Some(101));
+}
+
+#[test]
+fn test_strace_parse_line_group_stop() {
+    let strace = parse_strace_line("100 1757048541.000000 --- stopped by SIGTSTP ---").unwrap();
+    assert_eq!(strace.pid, 100);
+    let Event::GroupStop { signal } = strace.event else {
+        panic!("expected group-stop event, got {:?}", strace.event);
+    };
+    assert_eq!(signal, "SIGTSTP");
+}
+
+#[test]
+fn test_strace_parse_line_no_return() {
+    let syscall = parse_strace_line_syscall("100 1757048541.000000 exit_group(0)   = ?").unwrap();
+    assert_eq!(syscall.name, "exit_group");
+    assert_eq!(syscall.args_string.value, "0");
+    assert_eq!(syscall.result_string.value, "?");
+    assert_eq!(syscall.duration, std::time::Duration::ZERO);
+}
+
+#[test]
+fn test_strace_stitch_superseded() {
+    let mut stitcher = Stitcher::default();
+
+    let unfinished = parse_strace_line(
+        r#"101 1757048541.000000 execve("/bin/true", ["true"], [] <unfinished ...>"#,
+    )
+    .unwrap();
+    assert!(matches!(stitcher.stitch(&unfinished), Stitched::Unfinished));
+
+    let superseded =
+        parse_strace_line("100 1757048541.000100 +++ superseded by execve in pid 101 +++").unwrap();
+    assert!(matches!(stitcher.stitch(&superseded), Stitched::Complete));
+
+    // The thread's `execve` gets resumed under the leader's pid
+    let resumed =
+        parse_strace_line("100 1757048541.000200 <... execve resumed>) = 0 <0.000200>").unwrap();
+    let Stitched::Resumed { line, timestamp } = stitcher.stitch(&resumed) else {
+        panic!("expected resumed line");
+    };
+    assert_eq!(
+        line,
+        r#"100 1757048541.000200 execve("/bin/true", ["true"], []) = 0 <0.000200>"#
+    );
+    assert_eq!(timestamp, jiff::Timestamp::constant(1757048541, 0));
+}
+
+#[test]
+fn test_strace_parse_line_timestamp_formats() {
+    let base_time = jiff::civil::date(2025, 9, 5)
+        .at(0, 0, 0, 0)
+        .to_zoned(jiff::tz::TimeZone::UTC)
+        .unwrap();
+    let mut parser = LineParser::new(ParserOptions {
+        timestamp_format: TimestampFormat::Auto,
+        base_time: Some(base_time),
+        pid: None,
+    });
+
+    // Unix timestamp, with nanosecond precision
+    let strace = parser
+        .parse_line("1234 1757048541.498563123 brk(NULL) = 0x555589aac000 <0.000007123>")
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        jiff::Timestamp::constant(1757048541, 498563123),
+    );
+
+    // Time of day, without and with fractional seconds
+    let strace = parser
+        .parse_line("1234 23:59:58 brk(NULL) = 0x555589aac000 <0.000007>")
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        "2025-09-05T23:59:58Z".parse::<jiff::Timestamp>().unwrap()
+    );
+    let strace = parser
+        .parse_line("1234 23:59:59.500000 brk(NULL) = 0x555589aac000 <0.000007>")
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        "2025-09-05T23:59:59.5Z".parse::<jiff::Timestamp>().unwrap()
+    );
+
+    // Time of day after midnight
+    let strace = parser
+        .parse_line("1234 00:00:01.000000 brk(NULL) = 0x555589aac000 <0.000007>")
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        "2025-09-06T00:00:01Z".parse::<jiff::Timestamp>().unwrap()
+    );
+
+    // Relative timestamps (padded with spaces)
+    let strace = parser
+        .parse_line("1234      0.250000 brk(NULL) = 0x555589aac000 <0.000007>")
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        "2025-09-06T00:00:01.25Z"
+            .parse::<jiff::Timestamp>()
+            .unwrap()
+    );
+    let strace = parser
+        .parse_line("1234      1.000000 +++ exited with 0 +++")
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        "2025-09-06T00:00:02.25Z"
+            .parse::<jiff::Timestamp>()
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_strace_parse_line_relative_timestamps() {
+    let base_time = jiff::civil::date(2025, 9, 5)
+        .at(12, 0, 0, 0)
+        .to_zoned(jiff::tz::TimeZone::UTC)
+        .unwrap();
+    let mut parser = LineParser::new(ParserOptions {
+        timestamp_format: TimestampFormat::Relative,
+        base_time: Some(base_time),
+        pid: None,
+    });
+
+    let strace = parser
+        .parse_line("1234      0.000000 brk(NULL) = 0x555589aac000 <0.000007>")
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        "2025-09-05T12:00:00Z".parse::<jiff::Timestamp>().unwrap()
+    );
+
+    let strace = parser
+        .parse_line("1234      0.000123 brk(NULL) = 0x555589aac000 <0.000007>")
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        "2025-09-05T12:00:00.000123Z"
+            .parse::<jiff::Timestamp>()
+            .unwrap()
+    );
+
+    // Re-parsing a line keeps its resolved timestamp
+    let strace = parser
+        .parse_line_at(
+            "1234      0.000123 brk(NULL) = 0x555589aac000 <0.000007>",
+            strace.timestamp,
+        )
+        .unwrap();
+    assert_eq!(
+        strace.timestamp,
+        "2025-09-05T12:00:00.000123Z"
+            .parse::<jiff::Timestamp>()
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_strace_parse_line_without_pid() {
+    let mut parser = LineParser::new(ParserOptions {
+        pid: Some(1234),
+        ..Default::default()
+    });
+
+    let strace = parser
+        .parse_line("1757048541.498563 brk(NULL)     = 0x555589aac000 <0.000007>")
+        .unwrap();
+    assert_eq!(strace.pid, 1234);
+    assert_eq!(
+        strace.timestamp,
+        jiff::Timestamp::constant(1757048541, 498563000),
+    );
+    let syscall = syscall_event(strace.event).unwrap();
+    assert_eq!(syscall.name, "brk");
+    assert_eq!(syscall.args_string.value, "NULL");
+
+    let strace = parser
+        .parse_line("1757467412.646253 +++ killed by SIGTERM +++")
+        .unwrap();
+    assert_eq!(strace.pid, 1234);
+    let Event::KilledBy { signal_string } = strace.event else {
+        panic!("expected killed-by event, got {:?}", strace.event);
+    };
+    assert_eq!(signal_string.value, "SIGTERM");
+}
+
+#[test]
+fn test_strace_parse_syscall_result() {
+    let syscall =
+        parse_strace_line_syscall("1234 1757048541.498563 close(3) = 0 <0.000007>").unwrap();
+    let result = syscall.result().unwrap();
+    assert_eq!(result.success_i32(), Some(0));
+    assert_eq!(result.error, None);
+    assert_eq!(result.message, None);
+    assert!(!result.is_failed());
+
+    let syscall = parse_strace_line_syscall(
+        r#"1234 1757048541.498563 openat(AT_FDCWD, "/nope", O_RDONLY) = -1 ENOENT (No such file or directory) <0.000007>"#,
+    )
+    .unwrap();
+    let result = syscall.result().unwrap();
+    assert_eq!(result.success_i32(), None);
+    assert_eq!(
+        result.error,
+        Some(SyscallError {
+            name: Some("ENOENT"),
+            number: Some(2),
+        })
+    );
+    assert_eq!(result.message, Some("No such file or directory"));
+    assert!(result.is_failed());
+
+    let syscall = parse_strace_line_syscall(
+        "1234 1757048541.498563 poll([{fd=3, events=POLLIN}], 1, 100) = 0 (Timeout) <0.100100>",
+    )
+    .unwrap();
+    let result = syscall.result().unwrap();
+    assert_eq!(result.success_i32(), Some(0));
+    assert_eq!(result.error, None);
+    assert_eq!(result.message, Some("Timeout"));
+    assert!(!result.is_failed());
+
+    // Byte counts of 2 GiB or more don't fit in an i32
+    let syscall = parse_strace_line_syscall(
+        r#"1234 1757048541.498563 write(3, "\0\0\0"..., 3221225472) = 3221225472 <1.000000>"#,
+    )
+    .unwrap();
+    let result = syscall.result().unwrap();
+    assert_eq!(result.success_i32(), None);
+    assert_eq!(result.success_i64(), Some(3221225472));
+
+    let syscall = parse_strace_line_syscall(
+        "1234 1757048541.498563 wait4(-1, 0x7ffd0, 0, NULL) = ? ERESTARTSYS (To be restarted if SA_RESTART is set) <0.000100>",
+    )
+    .unwrap();
+    let result = syscall.result().unwrap();
+    assert_eq!(result.returned, None);
+    assert_eq!(
+        result.error,
+        Some(SyscallError {
+            name: Some("ERESTARTSYS"),
+            number: Some(512),
+        })
+    );
+    assert_eq!(result.message, Some("To be restarted if SA_RESTART is set"));
+    assert!(!result.is_failed());
+
+    let syscall = parse_strace_line_syscall(
+        "1234 1757048541.498563 ioctl(3, 0xbad) = -1 (errno 1234) <0.000007>",
+    )
+    .unwrap();
+    let result = syscall.result().unwrap();
+    assert_eq!(
+        result.error,
+        Some(SyscallError {
+            name: None,
+            number: Some(1234),
+        })
+    );
+    assert!(result.is_failed());
+}